@@ -0,0 +1,165 @@
+/// Number of slots whose control bytes are matched at once.
+pub(crate) const GROUP: usize = 16;
+
+/// Control byte of an empty slot. Tags never have the high bit set, so empties can be found
+/// from the high bits alone.
+const EMPTY: u8 = 0x80;
+
+/// One control byte per slot: `EMPTY`, or a 7-bit tag taken from the hash of the slot's key.
+///
+/// Lookups compare the tag against a whole group of slots at a time and only touch the slots
+/// that match. The first `GROUP` bytes are mirrored after the last slot so a group starting
+/// anywhere in the table can be loaded without wrapping.
+#[derive(Debug, Default)]
+pub(crate) struct Control {
+  bytes: Vec<u8>,
+  slots: usize,
+}
+
+impl Clone for Control {
+  fn clone(&self) -> Self {
+    Self { bytes: self.bytes.clone(), slots: self.slots }
+  }
+
+  fn clone_from(&mut self, source: &Self) {
+    self.bytes.clone_from(&source.bytes);
+    self.slots = source.slots;
+  }
+}
+
+/// Bitmasks over the slots of a group, bit `n` standing for the `n`th slot from its start.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Group {
+  /// Slots whose tag equals the one searched for.
+  pub(crate) matches: u16,
+  /// Empty slots.
+  pub(crate) empties: u16,
+}
+
+impl Control {
+  pub(crate) fn with_slots(slots: usize) -> Self {
+    Self {
+      bytes: vec![EMPTY; Self::bytes_for(slots)],
+      slots,
+    }
+  }
+
+  pub(crate) fn try_with_slots(slots: usize) -> Result<Self, std::collections::TryReserveError> {
+    let mut bytes = Vec::new();
+    bytes.try_reserve_exact(Self::bytes_for(slots))?;
+    bytes.resize(Self::bytes_for(slots), EMPTY);
+    Ok(Self { bytes, slots })
+  }
+
+  pub(crate) fn heap_size(&self) -> usize {
+    self.bytes.capacity()
+  }
+
+  fn bytes_for(slots: usize) -> usize {
+    if slots == 0 {
+      0
+    } else {
+      slots + GROUP
+    }
+  }
+
+  /// The tag stored for a key with this hash. The top bits are used because the low ones
+  /// already decide the home slot.
+  pub(crate) fn tag(hash: usize) -> u8 {
+    (hash >> (usize::BITS - 7)) as u8
+  }
+
+  pub(crate) fn set(&mut self, index: usize, hash: usize) {
+    self.write(index, Self::tag(hash));
+  }
+
+  pub(crate) fn clear(&mut self, index: usize) {
+    self.write(index, EMPTY);
+  }
+
+  pub(crate) fn clear_all(&mut self) {
+    self.bytes.fill(EMPTY);
+  }
+
+  /// Writes the control byte of `index` and every mirrored copy of it.
+  fn write(&mut self, index: usize, byte: u8) {
+    let mut position = index;
+    while position < self.bytes.len() {
+      self.bytes[position] = byte;
+      position += self.slots;
+    }
+  }
+
+  /// Starts loading the control bytes of the group at `index` into cache.
+  pub(crate) fn prefetch(&self, index: usize) {
+    prefetch(&self.bytes[index]);
+  }
+
+  /// Matches `tag` against the `GROUP` slots starting at `index`, wrapping around the table.
+  pub(crate) fn group(&self, index: usize, tag: u8) -> Group {
+    let bytes: &[u8; GROUP] = self.bytes[index..index + GROUP].try_into().unwrap();
+    match_group(bytes, tag)
+  }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn match_group(bytes: &[u8; GROUP], tag: u8) -> Group {
+  use std::arch::x86_64::{_mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8};
+  // SAFETY: SSE2 is part of the x86_64 baseline, and `bytes` is exactly one 16-byte load.
+  unsafe {
+    let group = _mm_loadu_si128(bytes.as_ptr().cast());
+    Group {
+      matches: _mm_movemask_epi8(_mm_cmpeq_epi8(group, _mm_set1_epi8(tag as i8))) as u16,
+      empties: _mm_movemask_epi8(group) as u16,
+    }
+  }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn match_group(bytes: &[u8; GROUP], tag: u8) -> Group {
+  use std::arch::aarch64::{uint8x16_t, vaddv_u8, vandq_u8, vceqq_u8, vcltq_s8, vdupq_n_s8, vdupq_n_u8, vget_high_u8, vget_low_u8, vld1q_u8, vreinterpretq_s8_u8};
+  // Reduces a vector of 0x00/0xFF lanes to one bit per lane.
+  unsafe fn movemask(lanes: uint8x16_t) -> u16 {
+    const WEIGHTS: [u8; GROUP] = [1, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128];
+    let weighted = vandq_u8(lanes, vld1q_u8(WEIGHTS.as_ptr()));
+    vaddv_u8(vget_low_u8(weighted)) as u16 | (vaddv_u8(vget_high_u8(weighted)) as u16) << 8
+  }
+  // SAFETY: NEON is part of the aarch64 baseline, and `bytes` is exactly one 16-byte load.
+  unsafe {
+    let group = vld1q_u8(bytes.as_ptr());
+    Group {
+      matches: movemask(vceqq_u8(group, vdupq_n_u8(tag))),
+      empties: movemask(vcltq_s8(vreinterpretq_s8_u8(group), vdupq_n_s8(0))),
+    }
+  }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn match_group(bytes: &[u8; GROUP], tag: u8) -> Group {
+  let mut group = Group { matches: 0, empties: 0 };
+  for (offset, &byte) in bytes.iter().enumerate() {
+    group.matches |= ((byte == tag) as u16) << offset;
+    group.empties |= ((byte & EMPTY != 0) as u16) << offset;
+  }
+  group
+}
+
+/// Hints the CPU to load the cache line holding `value`, so a later read does not stall on it.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+pub(crate) fn prefetch<T>(value: &T) {
+  use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+  // SAFETY: SSE is part of the x86_64 baseline, and prefetching a valid reference cannot fault.
+  unsafe { _mm_prefetch::<_MM_HINT_T0>((value as *const T).cast()) }
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+pub(crate) fn prefetch<T>(value: &T) {
+  // SAFETY: `prfm` is only a hint; it reads no memory architecturally and cannot fault.
+  unsafe { std::arch::asm!("prfm pldl1keep, [{}]", in(reg) value as *const T, options(nostack, readonly, preserves_flags)) }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline(always)]
+pub(crate) fn prefetch<T>(_value: &T) {}
@@ -0,0 +1,142 @@
+use crate::{grow_until_placed, hash, table_hash, Hashable};
+
+/// Evictions an insertion may cause before the table is grown instead.
+const MAX_KICKS: usize = 32;
+
+/// Map using cuckoo hashing: each key has one candidate slot in each of two tables, so a lookup
+/// probes at most two slots.
+///
+/// Both slots come from the key's mixed hash, the second after mixing it once more. Insertion
+/// evicts whichever entry sits in the way into its other slot, growing both tables when that
+/// keeps cycling; keys with equal full hashes share both slots, so no more than two of them can
+/// be stored and inserting a third panics.
+pub struct CuckooMap<Key, Value> {
+  tables: [Vec<Option<(Key, Value)>>; 2],
+  len: usize,
+}
+
+impl<Key, Value> Default for CuckooMap<Key, Value> where Key: Hashable + PartialEq {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Key, Value> CuckooMap<Key, Value> where Key: Hashable + PartialEq {
+  pub fn new() -> Self {
+    Self::with_capacity(64)
+  }
+
+  /// A map with `capacity` slots split between its two tables.
+  pub fn with_capacity(capacity: usize) -> Self {
+    let slots = capacity.div_ceil(2).max(1);
+    Self {
+      tables: [(); 2].map(|_| std::iter::repeat_with(|| None).take(slots).collect()),
+      len: 0,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.tables[0].len() * 2
+  }
+
+  /// The slot `key` may occupy in `table`.
+  fn slot(&self, table: usize, key: &Key) -> usize {
+    let hash = table_hash(key.hash());
+    let hash = if table == 0 { hash } else { hash::splitmix64(hash as u64) as usize };
+    hash % self.tables[table].len()
+  }
+
+  /// Table and slot holding `key`.
+  fn find(&self, key: &Key) -> Option<(usize, usize)> {
+    (0..2)
+      .map(|table| (table, self.slot(table, key)))
+      .find(|&(table, index)| {
+        matches!(&self.tables[table][index], Some((stored, _)) if stored == key)
+      })
+  }
+
+  pub fn get(&self, key: &Key) -> Option<&Value> {
+    let (table, index) = self.find(key)?;
+    let (_, value) = self.tables[table][index].as_ref()?;
+    Some(value)
+  }
+
+  pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+    let (table, index) = self.find(key)?;
+    let (_, value) = self.tables[table][index].as_mut()?;
+    Some(value)
+  }
+
+  pub fn contains_key(&self, key: &Key) -> bool {
+    self.find(key).is_some()
+  }
+
+  /// Inserts `value` under `key`, returning the value it replaced, if any.
+  pub fn insert(&mut self, key: Key, value: Value) -> Option<Value> {
+    if let Some(old) = self.get_mut(&key) {
+      return Some(std::mem::replace(old, value));
+    }
+    self.insert_unique((key, value));
+    None
+  }
+
+  pub fn remove(&mut self, key: &Key) -> Option<Value> {
+    let (table, index) = self.find(key)?;
+    self.len -= 1;
+    self.tables[table][index].take().map(|(_, value)| value)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+    self.tables.iter().flatten().flatten().map(|(key, value)| (key, value))
+  }
+
+  fn insert_unique(&mut self, entry: (Key, Value)) {
+    // Eviction chains get long quickly once more than half of the slots are taken.
+    if (self.len + 1) * 2 > self.capacity() {
+      self.grow(Vec::new());
+    }
+    if let Err(homeless) = self.try_place(entry) {
+      self.grow(vec![homeless]);
+    }
+    self.len += 1;
+  }
+
+  /// Stores `entry`, moving entries in the way to their other slot, or hands back the entry left
+  /// without a slot after `MAX_KICKS` evictions.
+  fn try_place(&mut self, mut entry: (Key, Value)) -> Result<(), (Key, Value)> {
+    let mut table = 0;
+    for _ in 0..=MAX_KICKS {
+      let index = self.slot(table, &entry.0);
+      match self.tables[table][index].replace(entry) {
+        None => return Ok(()),
+        Some(evicted) => entry = evicted,
+      }
+      table = 1 - table;
+    }
+    Err(entry)
+  }
+
+  /// Moves every entry, along with `pending`, into larger tables.
+  ///
+  /// Panics if even the largest tables tried leave an entry without a slot: three or more keys
+  /// then share a full hash.
+  fn grow(&mut self, mut pending: Vec<(Key, Value)>) {
+    let len = self.len;
+    let capacity = self.capacity();
+    pending.extend(std::mem::take(&mut self.tables).into_iter().flatten().flatten());
+    let entries = |map: Self| map.tables.into_iter().flatten().flatten();
+    let grown = grow_until_placed(capacity, pending, Self::with_capacity, Self::try_place, entries);
+    *self = grown.unwrap_or_else(|| {
+      panic!("CuckooMap: three or more keys share a hash, so no table fits them")
+    });
+    self.len = len;
+  }
+}
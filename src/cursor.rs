@@ -0,0 +1,51 @@
+use crate::{HashMap, Hashable};
+
+/// Walks the occupied slots of a `HashMap`, allowing the current entry to be replaced or
+/// removed mid-traversal. Obtained with `HashMap::cursor_mut`.
+///
+/// Every entry present when the cursor is created is visited exactly once.
+pub struct CursorMut<'a, Key, Value> {
+  pub(crate) map: &'a mut HashMap<Key, Value>,
+  pub(crate) start: usize,
+  pub(crate) visited: usize,
+  pub(crate) current: Option<usize>,
+}
+
+impl<Key, Value> CursorMut<'_, Key, Value> where Key: Hashable + PartialEq {
+  /// Advances to the next entry and returns it, or `None` once every entry has been visited.
+  #[allow(clippy::should_implement_trait)]
+  pub fn next(&mut self) -> Option<(&Key, &mut Value)> {
+    if self.current.take().is_some() {
+      self.visited += 1;
+    }
+    let len = self.map.entries.len();
+    while self.visited < len {
+      let index = (self.start + self.visited) % len;
+      if self.map.entries.is_occupied(index) {
+        self.current = Some(index);
+        return self.current();
+      }
+      self.visited += 1;
+    }
+    None
+  }
+
+  /// The entry the cursor is on, or `None` before the first `next` and after a removal.
+  pub fn current(&mut self) -> Option<(&Key, &mut Value)> {
+    self.map.entries.get_mut(self.current?)
+  }
+
+  /// Replaces the value of the current entry, returning the old one.
+  pub fn replace_current(&mut self, value: Value) -> Option<Value> {
+    let (_, current) = self.current()?;
+    Some(std::mem::replace(current, value))
+  }
+
+  /// Removes the current entry; the cursor then sits between it and the next entry.
+  pub fn remove_current(&mut self) -> Option<(Key, Value)> {
+    // The backward shift may pull an unvisited entry into this slot, so the position is not
+    // advanced and `next` looks at the same slot again.
+    let index = self.current.take()?;
+    Some(self.map.remove_at(index))
+  }
+}
@@ -0,0 +1,176 @@
+use std::fmt::{self, Display};
+
+use crate::{CuckooMap, HashMap, HopscotchMap, SmallMap};
+
+/// Map implementation driven by the differential harness.
+///
+/// Keys and values are plain `u64`s drawn from a small range so that updates and collisions are
+/// frequent. Implement this for a new backend to run it against `std::collections::HashMap`.
+pub trait Backend {
+  fn insert(&mut self, key: u64, value: u64) -> Option<u64>;
+  fn get(&self, key: &u64) -> Option<u64>;
+  fn remove(&mut self, key: &u64) -> Option<u64>;
+  fn len(&self) -> usize;
+  fn entries(&self) -> Vec<(u64, u64)>;
+}
+
+impl Backend for HashMap<u64, u64> {
+  fn insert(&mut self, key: u64, value: u64) -> Option<u64> {
+    HashMap::insert(self, key, value)
+  }
+
+  fn get(&self, key: &u64) -> Option<u64> {
+    HashMap::get(self, key).copied()
+  }
+
+  fn remove(&mut self, key: &u64) -> Option<u64> {
+    HashMap::remove(self, key)
+  }
+
+  fn len(&self) -> usize {
+    HashMap::len(self)
+  }
+
+  fn entries(&self) -> Vec<(u64, u64)> {
+    self.iter().map(|(key, value)| (*key, *value)).collect()
+  }
+}
+
+impl Backend for CuckooMap<u64, u64> {
+  fn insert(&mut self, key: u64, value: u64) -> Option<u64> {
+    CuckooMap::insert(self, key, value)
+  }
+
+  fn get(&self, key: &u64) -> Option<u64> {
+    CuckooMap::get(self, key).copied()
+  }
+
+  fn remove(&mut self, key: &u64) -> Option<u64> {
+    CuckooMap::remove(self, key)
+  }
+
+  fn len(&self) -> usize {
+    CuckooMap::len(self)
+  }
+
+  fn entries(&self) -> Vec<(u64, u64)> {
+    self.iter().map(|(key, value)| (*key, *value)).collect()
+  }
+}
+
+impl Backend for HopscotchMap<u64, u64> {
+  fn insert(&mut self, key: u64, value: u64) -> Option<u64> {
+    HopscotchMap::insert(self, key, value)
+  }
+
+  fn get(&self, key: &u64) -> Option<u64> {
+    HopscotchMap::get(self, key).copied()
+  }
+
+  fn remove(&mut self, key: &u64) -> Option<u64> {
+    HopscotchMap::remove(self, key)
+  }
+
+  fn len(&self) -> usize {
+    HopscotchMap::len(self)
+  }
+
+  fn entries(&self) -> Vec<(u64, u64)> {
+    self.iter().map(|(key, value)| (*key, *value)).collect()
+  }
+}
+
+impl<const N: usize> Backend for SmallMap<u64, u64, N> {
+  fn insert(&mut self, key: u64, value: u64) -> Option<u64> {
+    SmallMap::insert(self, key, value)
+  }
+
+  fn get(&self, key: &u64) -> Option<u64> {
+    SmallMap::get(self, key).copied()
+  }
+
+  fn remove(&mut self, key: &u64) -> Option<u64> {
+    SmallMap::remove(self, key)
+  }
+
+  fn len(&self) -> usize {
+    SmallMap::len(self)
+  }
+
+  fn entries(&self) -> Vec<(u64, u64)> {
+    self.iter().map(|(key, value)| (*key, *value)).collect()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+  Insert(u64, u64),
+  Get(u64),
+  Remove(u64),
+}
+
+/// First step at which the backend disagreed with the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+  pub step: usize,
+  /// The operation whose result differed, or `None` when every result matched but the final
+  /// contents did not.
+  pub op: Option<Op>,
+  pub expected: String,
+  pub actual: String,
+}
+
+impl Display for Divergence {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.op {
+      Some(op) => write!(f, "step {}: {:?} expected {} but got {}", self.step, op, self.expected, self.actual),
+      None => write!(f, "after {} steps: final entries expected {} but got {}", self.step, self.expected, self.actual),
+    }
+  }
+}
+
+/// SplitMix64, so runs are reproducible from a seed without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+  }
+}
+
+/// Runs `steps` random operations against `backend` and a `std` model, comparing every result.
+pub fn run<B: Backend>(backend: &mut B, seed: u64, steps: usize, key_range: u64) -> Result<(), Divergence> {
+  let mut rng = Rng(seed);
+  let mut model = std::collections::HashMap::<u64, u64>::new();
+  for step in 0..steps {
+    let key = rng.next() % key_range.max(1);
+    let op = match rng.next() % 3 {
+      0 => Op::Insert(key, rng.next()),
+      1 => Op::Get(key),
+      _ => Op::Remove(key),
+    };
+    let (expected, actual) = match op {
+      Op::Insert(key, value) => (format!("{:?}", model.insert(key, value)), format!("{:?}", backend.insert(key, value))),
+      Op::Get(key) => (format!("{:?}", model.get(&key).copied()), format!("{:?}", backend.get(&key))),
+      Op::Remove(key) => (format!("{:?}", model.remove(&key)), format!("{:?}", backend.remove(&key))),
+    };
+    if expected != actual {
+      return Err(Divergence { step, op: Some(op), expected, actual });
+    }
+    if model.len() != backend.len() {
+      return Err(Divergence { step, op: Some(op), expected: format!("len {}", model.len()), actual: format!("len {}", backend.len()) });
+    }
+  }
+  let mut expected: Vec<(u64, u64)> = model.into_iter().collect();
+  let mut actual = backend.entries();
+  expected.sort_unstable();
+  actual.sort_unstable();
+  if expected != actual {
+    return Err(Divergence { step: steps, op: None, expected: format!("{:?}", expected), actual: format!("{:?}", actual) });
+  }
+  Ok(())
+}
@@ -0,0 +1,219 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Display};
+
+use crate::{HashMap, Hashable};
+
+/// A view into a single slot of a `HashMap`, obtained with `HashMap::entry`.
+pub enum Entry<'a, Key, Value> {
+  Occupied(OccupiedEntry<'a, Key, Value>),
+  Vacant(VacantEntry<'a, Key, Value>),
+}
+
+pub struct OccupiedEntry<'a, Key, Value> {
+  pub(crate) map: &'a mut HashMap<Key, Value>,
+  pub(crate) index: usize,
+}
+
+/// Holds a missing `key` and its hash; the table grows, if full, when the entry is inserted.
+pub struct VacantEntry<'a, Key, Value> {
+  pub(crate) map: &'a mut HashMap<Key, Value>,
+  pub(crate) key: Key,
+  pub(crate) hash: usize,
+}
+
+/// Like `Entry`, but holds a borrowed key that is only converted to an owned `Key` when a
+/// vacant entry is filled. Obtained with `HashMap::entry_ref`.
+pub enum EntryRef<'a, 'q, Key, Q: ?Sized, Value> {
+  Occupied(OccupiedEntry<'a, Key, Value>),
+  Vacant(VacantEntryRef<'a, 'q, Key, Q, Value>),
+}
+
+pub struct VacantEntryRef<'a, 'q, Key, Q: ?Sized, Value> {
+  pub(crate) map: &'a mut HashMap<Key, Value>,
+  pub(crate) key: &'q Q,
+  pub(crate) hash: usize,
+}
+
+impl<'a, Key, Value> Entry<'a, Key, Value> where Key: Hashable + PartialEq {
+  pub fn key(&self) -> &Key {
+    match self {
+      Entry::Occupied(entry) => entry.key(),
+      Entry::Vacant(entry) => entry.key(),
+    }
+  }
+
+  pub fn or_insert(self, default: Value) -> &'a mut Value {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default),
+    }
+  }
+
+  pub fn or_insert_with<F: FnOnce() -> Value>(self, default: F) -> &'a mut Value {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default()),
+    }
+  }
+
+  pub fn or_insert_with_key<F: FnOnce(&Key) -> Value>(self, default: F) -> &'a mut Value {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => {
+        let value = default(&entry.key);
+        entry.insert(value)
+      }
+    }
+  }
+
+  pub fn or_default(self) -> &'a mut Value where Value: Default {
+    self.or_insert_with(Value::default)
+  }
+
+  /// Applies `modify` to the value if the entry is occupied.
+  pub fn and_modify<F: FnOnce(&mut Value)>(mut self, modify: F) -> Self {
+    if let Entry::Occupied(entry) = &mut self {
+      modify(entry.get_mut());
+    }
+    self
+  }
+}
+
+impl<'a, Key, Value> OccupiedEntry<'a, Key, Value> where Key: Hashable + PartialEq {
+  pub fn key(&self) -> &Key {
+    self.map.entries.key(self.index)
+  }
+
+  pub fn get(&self) -> &Value {
+    self.map.entries.value(self.index)
+  }
+
+  pub fn get_mut(&mut self) -> &mut Value {
+    self.map.entries.value_mut(self.index)
+  }
+
+  pub fn into_mut(self) -> &'a mut Value {
+    self.map.entries.value_mut(self.index)
+  }
+
+  /// Replaces the value, returning the old one.
+  pub fn insert(&mut self, value: Value) -> Value {
+    std::mem::replace(self.get_mut(), value)
+  }
+
+  pub fn remove(self) -> Value {
+    self.remove_entry().1
+  }
+
+  pub fn remove_entry(self) -> (Key, Value) {
+    let removed = self.map.remove_at(self.index);
+    self.map.shrink_if_sparse();
+    removed
+  }
+
+  /// Takes the entry out and passes it to `replace`, which returns the value to store back
+  /// under the same key, or `None` to leave the key removed.
+  ///
+  /// Returns the entry for the key afterwards: occupied by the new value, or vacant.
+  pub fn replace_entry_with<F: FnOnce(&Key, Value) -> Option<Value>>(self, replace: F) -> Entry<'a, Key, Value> {
+    let hash = self.map.entries.hashes[self.index];
+    let (key, value) = self.map.remove_at(self.index);
+    match replace(&key, value) {
+      // The removal freed a slot, so the entry goes back without growing the table.
+      Some(value) => {
+        let index = self.map.place(hash, key, value);
+        Entry::Occupied(OccupiedEntry { map: self.map, index })
+      }
+      None => {
+        self.map.shrink_if_sparse();
+        Entry::Vacant(VacantEntry { map: self.map, key, hash })
+      }
+    }
+  }
+}
+
+impl<'a, Key, Value> VacantEntry<'a, Key, Value> where Key: Hashable + PartialEq {
+  pub fn key(&self) -> &Key {
+    &self.key
+  }
+
+  pub fn into_key(self) -> Key {
+    self.key
+  }
+
+  pub fn insert(self, value: Value) -> &'a mut Value {
+    self.map.insert_absent(self.hash, self.key, value)
+  }
+}
+
+impl<'a, 'q, Key, Q: ?Sized, Value> EntryRef<'a, 'q, Key, Q, Value> where Key: Hashable + PartialEq + Borrow<Q> + From<&'q Q> {
+  pub fn key(&self) -> &Q {
+    match self {
+      EntryRef::Occupied(entry) => entry.key().borrow(),
+      EntryRef::Vacant(entry) => entry.key,
+    }
+  }
+
+  pub fn or_insert(self, default: Value) -> &'a mut Value {
+    match self {
+      EntryRef::Occupied(entry) => entry.into_mut(),
+      EntryRef::Vacant(entry) => entry.insert(default),
+    }
+  }
+
+  pub fn or_insert_with<F: FnOnce() -> Value>(self, default: F) -> &'a mut Value {
+    match self {
+      EntryRef::Occupied(entry) => entry.into_mut(),
+      EntryRef::Vacant(entry) => entry.insert(default()),
+    }
+  }
+
+  pub fn or_default(self) -> &'a mut Value where Value: Default {
+    self.or_insert_with(Value::default)
+  }
+
+  /// Applies `modify` to the value if the entry is occupied.
+  pub fn and_modify<F: FnOnce(&mut Value)>(mut self, modify: F) -> Self {
+    if let EntryRef::Occupied(entry) = &mut self {
+      modify(entry.get_mut());
+    }
+    self
+  }
+}
+
+impl<'a, 'q, Key, Q: ?Sized, Value> VacantEntryRef<'a, 'q, Key, Q, Value> where Key: Hashable + PartialEq + From<&'q Q> {
+  pub fn key(&self) -> &'q Q {
+    self.key
+  }
+
+  /// Converts the borrowed key into an owned `Key` and stores it with `value`.
+  pub fn insert(self, value: Value) -> &'a mut Value {
+    self.map.insert_absent(self.hash, Key::from(self.key), value)
+  }
+}
+
+/// Error returned by `HashMap::try_insert` when the key is already present.
+pub struct OccupiedError<'a, Key, Value> {
+  /// The entry of the existing key.
+  pub entry: OccupiedEntry<'a, Key, Value>,
+  /// The value that was not inserted.
+  pub value: Value,
+}
+
+impl<Key, Value> Debug for OccupiedError<'_, Key, Value> where Key: Hashable + Debug + PartialEq, Value: Debug {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("OccupiedError")
+      .field("key", self.entry.key())
+      .field("old_value", self.entry.get())
+      .field("new_value", &self.value)
+      .finish()
+  }
+}
+
+impl<Key, Value> Display for OccupiedError<'_, Key, Value> where Key: Hashable + Debug + PartialEq, Value: Debug {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "failed to insert {:?}, key {:?} already exists with value {:?}", self.value, self.entry.key(), self.entry.get())
+  }
+}
+
+impl<Key, Value> std::error::Error for OccupiedError<'_, Key, Value> where Key: Hashable + Debug + PartialEq, Value: Debug {}
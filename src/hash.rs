@@ -0,0 +1,307 @@
+use crate::Hashable;
+
+/// djb2, the original string hash of this crate. Fast but weak on similar keys.
+pub fn djb2(bytes: &[u8]) -> u64 {
+  let mut hash: u64 = 5381;
+  for &byte in bytes {
+    hash = (hash << 5).wrapping_add(hash).wrapping_add(byte as u64);
+  }
+  hash
+}
+
+const XXH_PRIME_1: u64 = 0x9E3779B185EBCA87;
+const XXH_PRIME_2: u64 = 0xC2B2AE3D27D4EB4F;
+const XXH_PRIME_3: u64 = 0x165667B19E3779F9;
+const XXH_PRIME_4: u64 = 0x85EBCA77C2B2AE63;
+const XXH_PRIME_5: u64 = 0x27D4EB2F165667C5;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+  u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8]) -> u64 {
+  u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64
+}
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+  acc.wrapping_add(input.wrapping_mul(XXH_PRIME_2)).rotate_left(31).wrapping_mul(XXH_PRIME_1)
+}
+
+fn xxh64_merge(acc: u64, value: u64) -> u64 {
+  (acc ^ xxh64_round(0, value)).wrapping_mul(XXH_PRIME_1).wrapping_add(XXH_PRIME_4)
+}
+
+/// XXH64 of `bytes`.
+pub fn xxh64(bytes: &[u8], seed: u64) -> u64 {
+  let mut rest = bytes;
+  let mut hash = if bytes.len() >= 32 {
+    let mut lanes = [
+      seed.wrapping_add(XXH_PRIME_1).wrapping_add(XXH_PRIME_2),
+      seed.wrapping_add(XXH_PRIME_2),
+      seed,
+      seed.wrapping_sub(XXH_PRIME_1),
+    ];
+    while rest.len() >= 32 {
+      for (lane, chunk) in lanes.iter_mut().zip(rest.chunks_exact(8)) {
+        *lane = xxh64_round(*lane, read_u64(chunk));
+      }
+      rest = &rest[32..];
+    }
+    let mut hash = lanes[0].rotate_left(1)
+      .wrapping_add(lanes[1].rotate_left(7))
+      .wrapping_add(lanes[2].rotate_left(12))
+      .wrapping_add(lanes[3].rotate_left(18));
+    for lane in lanes {
+      hash = xxh64_merge(hash, lane);
+    }
+    hash
+  } else {
+    seed.wrapping_add(XXH_PRIME_5)
+  };
+  hash = hash.wrapping_add(bytes.len() as u64);
+  while rest.len() >= 8 {
+    hash ^= xxh64_round(0, read_u64(rest));
+    hash = hash.rotate_left(27).wrapping_mul(XXH_PRIME_1).wrapping_add(XXH_PRIME_4);
+    rest = &rest[8..];
+  }
+  if rest.len() >= 4 {
+    hash ^= read_u32(rest).wrapping_mul(XXH_PRIME_1);
+    hash = hash.rotate_left(23).wrapping_mul(XXH_PRIME_2).wrapping_add(XXH_PRIME_3);
+    rest = &rest[4..];
+  }
+  for &byte in rest {
+    hash ^= (byte as u64).wrapping_mul(XXH_PRIME_5);
+    hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME_1);
+  }
+  hash ^= hash >> 33;
+  hash = hash.wrapping_mul(XXH_PRIME_2);
+  hash ^= hash >> 29;
+  hash = hash.wrapping_mul(XXH_PRIME_3);
+  hash ^ (hash >> 32)
+}
+
+const WY_SECRET: [u64; 4] = [0x2d358dccaa6c78a5, 0x8bb84b93962eacc9, 0x4b33a62ed433d4a3, 0x4d5a2da51de1aa47];
+
+fn wymum(a: u64, b: u64) -> (u64, u64) {
+  let product = (a as u128) * (b as u128);
+  (product as u64, (product >> 64) as u64)
+}
+
+fn wymix(a: u64, b: u64) -> u64 {
+  let (low, high) = wymum(a, b);
+  low ^ high
+}
+
+/// wyhash (final version 4) of `bytes`.
+pub fn wyhash(bytes: &[u8], seed: u64) -> u64 {
+  let length = bytes.len();
+  let mut seed = seed ^ wymix(seed ^ WY_SECRET[0], WY_SECRET[1]);
+  let (a, b) = if length <= 16 {
+    if length >= 4 {
+      let shift = (length >> 3) << 2;
+      (
+        (read_u32(bytes) << 32) | read_u32(&bytes[shift..]),
+        (read_u32(&bytes[length - 4..]) << 32) | read_u32(&bytes[length - 4 - shift..]),
+      )
+    } else if length > 0 {
+      (((bytes[0] as u64) << 16) | ((bytes[length >> 1] as u64) << 8) | bytes[length - 1] as u64, 0)
+    } else {
+      (0, 0)
+    }
+  } else {
+    let mut rest = bytes;
+    if rest.len() >= 48 {
+      let (mut see1, mut see2) = (seed, seed);
+      while rest.len() >= 48 {
+        seed = wymix(read_u64(rest) ^ WY_SECRET[1], read_u64(&rest[8..]) ^ seed);
+        see1 = wymix(read_u64(&rest[16..]) ^ WY_SECRET[2], read_u64(&rest[24..]) ^ see1);
+        see2 = wymix(read_u64(&rest[32..]) ^ WY_SECRET[3], read_u64(&rest[40..]) ^ see2);
+        rest = &rest[48..];
+      }
+      seed ^= see1 ^ see2;
+    }
+    while rest.len() > 16 {
+      seed = wymix(read_u64(rest) ^ WY_SECRET[1], read_u64(&rest[8..]) ^ seed);
+      rest = &rest[16..];
+    }
+    // The last 16 bytes of the input, which may overlap bytes already consumed.
+    let tail = &bytes[length - 16..];
+    (read_u64(tail), read_u64(&tail[8..]))
+  };
+  let (a, b) = wymum(a ^ WY_SECRET[1], b ^ seed);
+  wymix(a ^ WY_SECRET[0] ^ length as u64, b ^ WY_SECRET[1])
+}
+
+/// Reflected CRC-32C (Castagnoli) polynomial, the one implemented by SSE4.2 and ARMv8 CRC.
+const CRC32C_POLYNOMIAL: u32 = 0x82F63B78;
+
+const CRC32C_TABLE: [u32; 256] = {
+  let mut table = [0u32; 256];
+  let mut byte = 0;
+  while byte < 256 {
+    let mut crc = byte as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 == 1 { (crc >> 1) ^ CRC32C_POLYNOMIAL } else { crc >> 1 };
+      bit += 1;
+    }
+    table[byte] = crc;
+    byte += 1;
+  }
+  table
+};
+
+/// CRC-32C of `bytes`, using the CPU's CRC instructions when available.
+pub fn crc32c(bytes: &[u8]) -> u32 {
+  #[cfg(target_arch = "x86_64")]
+  {
+    if std::is_x86_feature_detected!("sse4.2") {
+      // SAFETY: the required CPU feature was detected just above.
+      return unsafe { crc32c_sse42(bytes) };
+    }
+  }
+  #[cfg(target_arch = "aarch64")]
+  {
+    if std::arch::is_aarch64_feature_detected!("crc") {
+      // SAFETY: the required CPU feature was detected just above.
+      return unsafe { crc32c_arm(bytes) };
+    }
+  }
+  crc32c_scalar(bytes)
+}
+
+pub fn crc32c_scalar(bytes: &[u8]) -> u32 {
+  let mut crc = !0u32;
+  for &byte in bytes {
+    crc = (crc >> 8) ^ CRC32C_TABLE[((crc ^ byte as u32) & 0xFF) as usize];
+  }
+  !crc
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn crc32c_sse42(bytes: &[u8]) -> u32 {
+  use std::arch::x86_64::{_mm_crc32_u64, _mm_crc32_u8};
+  let mut crc = !0u64;
+  let mut chunks = bytes.chunks_exact(8);
+  for chunk in &mut chunks {
+    crc = _mm_crc32_u64(crc, u64::from_le_bytes(chunk.try_into().unwrap()));
+  }
+  let mut crc = crc as u32;
+  for &byte in chunks.remainder() {
+    crc = _mm_crc32_u8(crc, byte);
+  }
+  !crc
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "crc")]
+unsafe fn crc32c_arm(bytes: &[u8]) -> u32 {
+  use std::arch::aarch64::{__crc32cb, __crc32cd};
+  let mut crc = !0u32;
+  let mut chunks = bytes.chunks_exact(8);
+  for chunk in &mut chunks {
+    crc = __crc32cd(crc, u64::from_le_bytes(chunk.try_into().unwrap()));
+  }
+  for &byte in chunks.remainder() {
+    crc = __crc32cb(crc, byte);
+  }
+  !crc
+}
+
+/// SplitMix64 finalizer: a bijection that spreads every input bit over the whole output.
+pub fn splitmix64(mut hash: u64) -> u64 {
+  hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+  hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+  hash ^ (hash >> 31)
+}
+
+/// Byte representation fed to the byte-oriented hash strategies.
+pub trait HashBytes {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R;
+}
+
+impl HashBytes for String {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(self.as_bytes())
+  }
+}
+
+impl HashBytes for &str {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(self.as_bytes())
+  }
+}
+
+impl HashBytes for &[u8] {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(self)
+  }
+}
+
+impl HashBytes for Vec<u8> {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(self)
+  }
+}
+
+impl HashBytes for u32 {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(&self.to_le_bytes())
+  }
+}
+
+impl HashBytes for u64 {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(&self.to_le_bytes())
+  }
+}
+
+impl HashBytes for usize {
+  fn with_bytes<R>(&self, hash: impl FnOnce(&[u8]) -> R) -> R {
+    hash(&self.to_le_bytes())
+  }
+}
+
+/// Key wrapper hashing the inner key with CRC-32C.
+///
+/// The map picks its hash through the key's `Hashable` impl, so this is how a key type opts
+/// into the hardware CRC hash: store `Crc32(key)` instead of `key`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Crc32<T>(pub T);
+
+impl<T: HashBytes> Hashable for Crc32<T> {
+  fn hash(&self) -> usize {
+    self.0.with_bytes(crc32c) as usize
+  }
+}
+
+/// Key wrapper hashing the inner key with XXH64.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct XxHash<T>(pub T);
+
+impl<T: HashBytes> Hashable for XxHash<T> {
+  fn hash(&self) -> usize {
+    self.0.with_bytes(|bytes| xxh64(bytes, 0)) as usize
+  }
+}
+
+/// Key wrapper hashing the inner key with wyhash, the hash `String` and `&str` keys use by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WyHash<T>(pub T);
+
+impl<T: HashBytes> Hashable for WyHash<T> {
+  fn hash(&self) -> usize {
+    self.0.with_bytes(|bytes| wyhash(bytes, 0)) as usize
+  }
+}
+
+/// Key wrapper hashing the inner key with djb2.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Djb2<T>(pub T);
+
+impl<T: HashBytes> Hashable for Djb2<T> {
+  fn hash(&self) -> usize {
+    self.0.with_bytes(djb2) as usize
+  }
+}
@@ -0,0 +1,174 @@
+use crate::{grow_until_placed, table_hash, Hashable};
+
+/// How far from its home bucket an entry may be stored; one bit per slot in a hop bitmap.
+const NEIGHBORHOOD: usize = 32;
+
+/// Open-addressing map using hopscotch hashing.
+///
+/// Every entry lives within `NEIGHBORHOOD` slots of its home bucket, and the home bucket's hop
+/// bitmap records which of those slots hold its entries, so a lookup touches at most one small
+/// neighborhood however full the table is. Insertion pays for this by moving entries closer to
+/// their homes to make room, growing the table when that is impossible; keys whose full hashes
+/// collide more than `NEIGHBORHOOD` times can therefore never all be stored, and inserting them
+/// panics.
+pub struct HopscotchMap<Key, Value> {
+  slots: Vec<Option<(Key, Value)>>,
+  /// Bit `n` of `hops[home]` is set when slot `home + n` holds an entry whose home is `home`.
+  hops: Vec<u32>,
+  len: usize,
+}
+
+impl<Key, Value> Default for HopscotchMap<Key, Value> where Key: Hashable + PartialEq {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Key, Value> HopscotchMap<Key, Value> where Key: Hashable + PartialEq {
+  pub fn new() -> Self {
+    Self::with_capacity(64)
+  }
+
+  /// A map with `capacity` slots, rounded up to at least one neighborhood.
+  pub fn with_capacity(capacity: usize) -> Self {
+    let slots = capacity.max(NEIGHBORHOOD);
+    Self {
+      slots: std::iter::repeat_with(|| None).take(slots).collect(),
+      hops: vec![0; slots],
+      len: 0,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  fn home(&self, key: &Key) -> usize {
+    table_hash(key.hash()) % self.slots.len()
+  }
+
+  /// Index of the slot holding `key`, found through the hop bitmap of its home bucket.
+  fn find(&self, key: &Key) -> Option<usize> {
+    let home = self.home(key);
+    let mut hops = self.hops[home];
+    while hops != 0 {
+      let index = (home + hops.trailing_zeros() as usize) % self.slots.len();
+      if matches!(&self.slots[index], Some((stored, _)) if stored == key) {
+        return Some(index);
+      }
+      hops &= hops - 1;
+    }
+    None
+  }
+
+  pub fn get(&self, key: &Key) -> Option<&Value> {
+    let (_, value) = self.slots[self.find(key)?].as_ref()?;
+    Some(value)
+  }
+
+  pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+    let index = self.find(key)?;
+    let (_, value) = self.slots[index].as_mut()?;
+    Some(value)
+  }
+
+  pub fn contains_key(&self, key: &Key) -> bool {
+    self.find(key).is_some()
+  }
+
+  /// Inserts `value` under `key`, returning the value it replaced, if any.
+  pub fn insert(&mut self, key: Key, value: Value) -> Option<Value> {
+    if let Some(old) = self.get_mut(&key) {
+      return Some(std::mem::replace(old, value));
+    }
+    self.insert_unique((key, value));
+    None
+  }
+
+  pub fn remove(&mut self, key: &Key) -> Option<Value> {
+    let index = self.find(key)?;
+    let home = self.home(key);
+    let offset = (index + self.slots.len() - home) % self.slots.len();
+    self.hops[home] &= !(1 << offset);
+    self.len -= 1;
+    self.slots[index].take().map(|(_, value)| value)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+    self.slots.iter().flatten().map(|(key, value)| (key, value))
+  }
+
+  fn insert_unique(&mut self, entry: (Key, Value)) {
+    // Past 90% occupancy, free slots get too far apart to hop into most neighborhoods.
+    if (self.len + 1) * 10 > self.slots.len() * 9 {
+      self.grow(Vec::new());
+    }
+    if let Err(rejected) = self.try_place(entry) {
+      self.grow(vec![rejected]);
+    }
+    self.len += 1;
+  }
+
+  /// Stores `entry` within the neighborhood of its home, or hands it back if no free slot can
+  /// be moved that close.
+  fn try_place(&mut self, entry: (Key, Value)) -> Result<(), (Key, Value)> {
+    let len = self.slots.len();
+    let home = self.home(&entry.0);
+    let free = (0..len).find(|distance| self.slots[(home + distance) % len].is_none());
+    let Some(mut distance) = free else {
+      return Err(entry);
+    };
+    while distance >= NEIGHBORHOOD {
+      match self.hop_back((home + distance) % len) {
+        Some(hopped) => distance -= hopped,
+        None => return Err(entry),
+      }
+    }
+    self.slots[(home + distance) % len] = Some(entry);
+    self.hops[home] |= 1 << distance;
+    Ok(())
+  }
+
+  /// Moves an entry from before the empty slot `free` into it without leaving its own
+  /// neighborhood, and returns how many slots closer the empty slot got.
+  fn hop_back(&mut self, free: usize) -> Option<usize> {
+    let len = self.slots.len();
+    // Trying the furthest bucket first moves the empty slot back the most.
+    for back in (1..NEIGHBORHOOD).rev() {
+      let bucket = (free + len - back) % len;
+      let movable = self.hops[bucket] & ((1 << back) - 1);
+      if movable != 0 {
+        let offset = movable.trailing_zeros() as usize;
+        let from = (bucket + offset) % len;
+        self.slots[free] = self.slots[from].take();
+        self.hops[bucket] = (self.hops[bucket] & !(1 << offset)) | 1 << back;
+        return Some(back - offset);
+      }
+    }
+    None
+  }
+
+  /// Moves every entry, along with `pending`, into a larger table.
+  ///
+  /// Panics if even the largest table tried has a neighborhood it cannot fit: more than
+  /// `NEIGHBORHOOD` keys then share a full hash.
+  fn grow(&mut self, mut pending: Vec<(Key, Value)>) {
+    let len = self.len;
+    let capacity = self.slots.len();
+    pending.extend(std::mem::take(&mut self.slots).into_iter().flatten());
+    let entries = |map: Self| map.slots.into_iter().flatten();
+    let grown = grow_until_placed(capacity, pending, Self::with_capacity, Self::try_place, entries);
+    *self = grown.unwrap_or_else(|| {
+      panic!("HopscotchMap: more than {} keys share a hash, so no table fits them", NEIGHBORHOOD)
+    });
+    self.len = len;
+  }
+}
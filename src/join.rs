@@ -0,0 +1,65 @@
+use crate::{HashMap, Hashable};
+
+/// Inner equi-join of two iterators on the keys computed by `left_key` and `right_key`.
+///
+/// The side whose `size_hint` is smaller is collected into a `HashMap`; the other side is
+/// streamed through it, so only the smaller input is ever held in memory. Joined pairs are
+/// yielded as `(left, right)` regardless of which side was built.
+pub fn hash_join<'a, Left, Right, Key, LeftIter, RightIter, LeftKey, RightKey>(
+  left: LeftIter,
+  right: RightIter,
+  left_key: LeftKey,
+  right_key: RightKey,
+) -> Box<dyn Iterator<Item = (Left, Right)> + 'a>
+where
+  LeftIter: IntoIterator<Item = Left>,
+  LeftIter::IntoIter: 'a,
+  RightIter: IntoIterator<Item = Right>,
+  RightIter::IntoIter: 'a,
+  Left: Clone + 'a,
+  Right: Clone + 'a,
+  Key: Hashable + PartialEq + 'a,
+  LeftKey: Fn(&Left) -> Key + 'a,
+  RightKey: Fn(&Right) -> Key + 'a,
+{
+  let (left, right) = (left.into_iter(), right.into_iter());
+  if left.size_hint().0 <= right.size_hint().0 {
+    let table = JoinTable::build(left, left_key);
+    Box::new(right.flat_map(move |item| table.probe(&right_key(&item), item)))
+  } else {
+    let table = JoinTable::build(right, right_key);
+    Box::new(left.flat_map(move |item| table.probe(&left_key(&item), item)).map(|(right, left)| (left, right)))
+  }
+}
+
+struct JoinTable<Key, Item> {
+  items: Vec<Item>,
+  index: HashMap<Key, Vec<usize>>,
+}
+
+impl<Key, Item> JoinTable<Key, Item> where Key: Hashable + PartialEq, Item: Clone {
+  fn build(items: impl Iterator<Item = Item>, key: impl Fn(&Item) -> Key) -> Self {
+    let mut table = Self {
+      items: vec![],
+      index: HashMap::new(),
+    };
+    for item in items {
+      let key = key(&item);
+      if let Some(positions) = table.index.get_mut(&key) {
+        positions.push(table.items.len());
+      } else {
+        table.index.insert(key, vec![table.items.len()]);
+      }
+      table.items.push(item);
+    }
+    table
+  }
+
+  /// Pairs `other` with every built item sharing its key.
+  fn probe<Other: Clone>(&self, key: &Key, other: Other) -> Vec<(Item, Other)> {
+    match self.index.get(key) {
+      Some(positions) => positions.iter().map(|&position| (self.items[position].clone(), other.clone())).collect(),
+      None => vec![],
+    }
+  }
+}
@@ -1,41 +1,290 @@
-use std::fmt::Debug;
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display};
+use std::iter::FusedIterator;
 
-#[derive(Debug, Default, Clone)]
-pub struct Entry<Key, Value> {
-  occupied: bool,
-  key: Key,
-  value: Value,
-}
+mod control;
+#[allow(dead_code)]
+mod cuckoo;
+#[allow(dead_code)]
+mod cursor;
+#[cfg(feature = "differential")]
+#[allow(dead_code)]
+mod differential;
+#[allow(dead_code)]
+mod entry;
+#[allow(dead_code)]
+pub mod hash;
+#[allow(dead_code)]
+mod hopscotch;
+#[allow(dead_code)]
+mod join;
+#[allow(dead_code)]
+mod memory;
+#[allow(dead_code)]
+mod nested;
+#[allow(dead_code)]
+mod nested_hash_map;
+mod occupancy;
+#[allow(dead_code)]
+mod overlay;
+mod profiling;
+mod repl;
+#[allow(dead_code)]
+mod raw_entry;
+#[cfg(feature = "rand")]
+#[allow(dead_code)]
+mod sampling;
+#[allow(dead_code)]
+mod small_map;
+#[allow(dead_code)]
+mod varmap;
+#[cfg(feature = "visualize")]
+mod visualize;
 
+pub use cuckoo::CuckooMap;
+pub use cursor::CursorMut;
+pub use entry::{Entry, EntryRef, OccupiedEntry, OccupiedError, VacantEntry, VacantEntryRef};
+use control::Control;
+use occupancy::Occupancy;
+pub use hopscotch::HopscotchMap;
+pub use join::hash_join;
+pub use memory::HeapSize;
+pub use nested::NestedMap;
+pub use nested_hash_map::NestedHashMap;
+pub use overlay::OverlayMap;
+pub use profiling::Metrics;
+pub use raw_entry::{RawEntryBuilder, RawEntryBuilderMut, RawEntryMut, RawVacantEntryMut};
+pub use small_map::SmallMap;
+pub use varmap::VarMap;
+
+/// The buckets of the table, stored as parallel arrays indexed by slot.
+///
+/// Probing reads only `hashes` and `keys`, so a failed lookup never pulls values into cache
+/// and more keys fit in each cache line.
 #[derive(Debug)]
+pub struct Slots<Key, Value> {
+  /// `Hashable::hash` of each key, kept so resizing and probing need not hash it again.
+  hashes: Vec<usize>,
+  /// The key of every occupied slot; `None` marks an empty one.
+  keys: Vec<Option<Key>>,
+  /// Set exactly where `keys` is.
+  values: Vec<Option<Value>>,
+}
+
+impl<Key, Value> Default for Slots<Key, Value> {
+  fn default() -> Self {
+    Slots { hashes: vec![], keys: vec![], values: vec![] }
+  }
+}
+
+impl<Key, Value> Clone for Slots<Key, Value> where Key: Clone, Value: Clone {
+  fn clone(&self) -> Self {
+    Slots { hashes: self.hashes.clone(), keys: self.keys.clone(), values: self.values.clone() }
+  }
+
+  fn clone_from(&mut self, source: &Self) {
+    self.hashes.clone_from(&source.hashes);
+    self.keys.clone_from(&source.keys);
+    self.values.clone_from(&source.values);
+  }
+}
+
+impl<Key, Value> Slots<Key, Value> {
+  /// `count` empty slots, without requiring `Clone` like `vec![None; count]` would.
+  fn vacant(count: usize) -> Self {
+    Slots {
+      hashes: vec![0; count],
+      keys: std::iter::repeat_with(|| None).take(count).collect(),
+      values: std::iter::repeat_with(|| None).take(count).collect(),
+    }
+  }
+
+  fn try_vacant(count: usize) -> Result<Self, std::collections::TryReserveError> {
+    let mut slots = Self::default();
+    slots.hashes.try_reserve_exact(count)?;
+    slots.keys.try_reserve_exact(count)?;
+    slots.values.try_reserve_exact(count)?;
+    slots.hashes.resize(count, 0);
+    slots.keys.resize_with(count, || None);
+    slots.values.resize_with(count, || None);
+    Ok(slots)
+  }
+
+  fn len(&self) -> usize {
+    self.keys.len()
+  }
+
+  fn is_empty(&self) -> bool {
+    self.keys.is_empty()
+  }
+
+  fn is_occupied(&self, index: usize) -> bool {
+    self.keys[index].is_some()
+  }
+
+  /// The key of an occupied slot.
+  fn key(&self, index: usize) -> &Key {
+    self.keys[index].as_ref().expect("slot is vacant")
+  }
+
+  /// The value of an occupied slot.
+  fn value(&self, index: usize) -> &Value {
+    self.values[index].as_ref().expect("slot is vacant")
+  }
+
+  fn value_mut(&mut self, index: usize) -> &mut Value {
+    self.values[index].as_mut().expect("slot is vacant")
+  }
+
+  /// The entry in slot `index`, if it is occupied.
+  fn get(&self, index: usize) -> Option<(&Key, &Value)> {
+    Some((self.keys[index].as_ref()?, self.values[index].as_ref()?))
+  }
+
+  fn get_mut(&mut self, index: usize) -> Option<(&Key, &mut Value)> {
+    Some((self.keys[index].as_ref()?, self.values[index].as_mut()?))
+  }
+
+  /// Stores an entry in the occupied slot `index`, returning the one it held.
+  fn replace(&mut self, index: usize, (hash, key, value): (usize, Key, Value)) -> Option<(usize, Key, Value)> {
+    let old_hash = std::mem::replace(&mut self.hashes[index], hash);
+    Some((old_hash, self.keys[index].replace(key)?, self.values[index].replace(value)?))
+  }
+
+  /// Empties slot `index`, returning its entry.
+  fn take(&mut self, index: usize) -> Option<(Key, Value)> {
+    Some((self.keys[index].take()?, self.values[index].take()?))
+  }
+
+  /// Starts loading the hash and key of slot `index` into cache.
+  fn prefetch(&self, index: usize) {
+    control::prefetch(&self.hashes[index]);
+    control::prefetch(&self.keys[index]);
+  }
+
+  fn swap(&mut self, a: usize, b: usize) {
+    self.hashes.swap(a, b);
+    self.keys.swap(a, b);
+    self.values.swap(a, b);
+  }
+
+  /// The occupied slots' entries, with their hashes, in slot order.
+  fn into_entries(self) -> impl Iterator<Item = (usize, Key, Value)> {
+    self.hashes.into_iter().zip(self.keys).zip(self.values).filter_map(|((hash, key), value)| Some((hash, key?, value?)))
+  }
+}
+
 pub struct HashMap<K, V> {
-  entries: Vec<Entry<K, V>>,
+  entries: Slots<K, V>,
+  occupancy: Occupancy,
+  control: Control,
   occupied: usize,
+  /// Fraction of the slots that may be occupied before the table grows, in millionths so the
+  /// limits are computed exactly.
+  max_load: u32,
+  /// Fraction of the slots below which removals shrink the table, in millionths; 0 never shrinks.
+  min_load: u32,
+  /// No slot below this one is occupied, so `pop` can start its scan here instead of at 0.
+  first_occupied: usize,
+  #[cfg(feature = "profiling")]
+  metrics: profiling::Counters,
+}
+
+const MILLION: u32 = 1_000_000;
+const DEFAULT_MAX_LOAD: u32 = MILLION / 2;
+
+/// Error returned by `try_reserve` and `try_with_capacity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryReserveError {
+  /// The requested number of slots does not fit in a `usize`, or their arrays would be larger
+  /// than the `isize::MAX` bytes any allocation is limited to.
+  CapacityOverflow,
+  /// The allocator could not provide the memory.
+  AllocError(std::collections::TryReserveError),
+}
+
+impl Display for TryReserveError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+      TryReserveError::AllocError(error) => write!(f, "{}", error),
+    }
+  }
 }
 
+impl std::error::Error for TryReserveError {}
+
 pub trait Hashable {
   fn hash(&self) -> usize;
 }
 
+/// The hash the table works with: `Hashable::hash` run through a finalizer, so identity hashes
+/// such as those of sequential integers still spread over the buckets and get distinct tags.
+fn table_hash(hash: usize) -> usize {
+  hash::splitmix64(hash as u64) as usize
+}
+
+/// Doublings in a row `grow_until_placed` tries before giving up.
+const MAX_GROWS: usize = 8;
+
+/// Builds a table twice the size of `capacity` and places every `pending` entry in it, doubling
+/// again whenever `place` hands one back; `entries` empties a table that fell short.
+///
+/// Cuckoo and hopscotch tables can refuse an entry however empty they are once too many keys
+/// share a hash, so after `MAX_GROWS` doublings this gives up and returns `None`.
+fn grow_until_placed<Table, Entry, Entries: IntoIterator<Item = Entry>>(
+  mut capacity: usize,
+  mut pending: Vec<Entry>,
+  build: impl Fn(usize) -> Table,
+  mut place: impl FnMut(&mut Table, Entry) -> Result<(), Entry>,
+  entries: impl Fn(Table) -> Entries,
+) -> Option<Table> {
+  for _ in 0..MAX_GROWS {
+    capacity *= 2;
+    let mut table = build(capacity);
+    let refused: Vec<Entry> = pending.drain(..).filter_map(|entry| place(&mut table, entry).err()).collect();
+    if refused.is_empty() {
+      return Some(table);
+    }
+    pending = refused;
+    pending.extend(entries(table));
+  }
+  None
+}
+
+impl Hashable for str {
+  fn hash(&self) -> usize {
+    hash::wyhash(self.as_bytes(), 0) as usize
+  }
+}
+
 impl Hashable for String {
   fn hash(&self) -> usize {
-    // djb2
-    let mut hash: usize = 5381;
-    for byte in self.bytes() {
-      hash = (hash << 5).wrapping_add(hash) + byte as usize;
-    }
-    hash
+    self.as_str().hash()
   }
 }
 
 impl Hashable for &str {
   fn hash(&self) -> usize {
-    // djb2
-    let mut hash: usize = 5381;
-    for byte in self.bytes() {
-      hash = (hash << 5).wrapping_add(hash) + byte as usize;
-    }
-    hash
+    (**self).hash()
+  }
+}
+
+impl Hashable for [u8] {
+  fn hash(&self) -> usize {
+    hash::wyhash(self, 0) as usize
+  }
+}
+
+impl Hashable for Vec<u8> {
+  fn hash(&self) -> usize {
+    self.as_slice().hash()
+  }
+}
+
+impl Hashable for &[u8] {
+  fn hash(&self) -> usize {
+    (**self).hash()
   }
 }
 
@@ -140,57 +389,336 @@ impl Hashable for bool {
 }
 
 pub struct HashMapIterator<'a, Key, Value> {
-  entries: &'a Vec<Entry<Key, Value>>,
+  entries: &'a Slots<Key, Value>,
+  occupancy: &'a Occupancy,
   current_index: usize,
+  remaining: usize,
 }
 
 impl<'a, Key, Value> Iterator for HashMapIterator<'a, Key, Value> {
   type Item = (&'a Key, &'a Value);
 
   fn next(&mut self) -> Option<Self::Item> {
-      while self.current_index < self.entries.len() {
-          let entry = &self.entries[self.current_index];
-          self.current_index += 1;
-          if entry.occupied {
-              return Some((&entry.key, &entry.value));
-          }
+      if self.remaining == 0 {
+        return None;
       }
-      None
+      let index = self.occupancy.next_from(self.current_index)?;
+      self.current_index = index + 1;
+      self.remaining -= 1;
+      self.entries.get(index)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<Key, Value> ExactSizeIterator for HashMapIterator<'_, Key, Value> {}
+
+impl<Key, Value> FusedIterator for HashMapIterator<'_, Key, Value> {}
+
+pub struct Keys<'a, Key, Value> {
+  inner: HashMapIterator<'a, Key, Value>,
+}
+
+impl<'a, Key, Value> Iterator for Keys<'a, Key, Value> {
+  type Item = &'a Key;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<Key, Value> ExactSizeIterator for Keys<'_, Key, Value> {}
+
+impl<Key, Value> FusedIterator for Keys<'_, Key, Value> {}
+
+pub struct Values<'a, Key, Value> {
+  inner: HashMapIterator<'a, Key, Value>,
+}
+
+impl<'a, Key, Value> Iterator for Values<'a, Key, Value> {
+  type Item = &'a Value;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<Key, Value> ExactSizeIterator for Values<'_, Key, Value> {}
+
+impl<Key, Value> FusedIterator for Values<'_, Key, Value> {}
+
+pub struct IterMut<'a, Key, Value> {
+  keys: std::slice::Iter<'a, Option<Key>>,
+  values: std::slice::IterMut<'a, Option<Value>>,
+  occupancy: &'a Occupancy,
+  /// Slot the `keys` and `values` iterators are at.
+  current_index: usize,
+  remaining: usize,
+}
+
+impl<'a, Key, Value> Iterator for IterMut<'a, Key, Value> {
+  type Item = (&'a Key, &'a mut Value);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.remaining == 0 {
+      return None;
+    }
+    // Slice iterators skip ahead in constant time, so only occupied slots are visited.
+    let index = self.occupancy.next_from(self.current_index)?;
+    let skip = index - self.current_index;
+    self.current_index = index + 1;
+    self.remaining -= 1;
+    Some((self.keys.nth(skip)?.as_ref()?, self.values.nth(skip)?.as_mut()?))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<Key, Value> ExactSizeIterator for IterMut<'_, Key, Value> {}
+
+impl<Key, Value> FusedIterator for IterMut<'_, Key, Value> {}
+
+pub struct IntoIter<Key, Value> {
+  entries: Slots<Key, Value>,
+  occupancy: Occupancy,
+  current_index: usize,
+  remaining: usize,
+}
+
+impl<Key, Value> Iterator for IntoIter<Key, Value> {
+  type Item = (Key, Value);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.remaining == 0 {
+      return None;
+    }
+    let index = self.occupancy.next_from(self.current_index)?;
+    self.current_index = index + 1;
+    self.remaining -= 1;
+    self.entries.take(index)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<Key, Value> ExactSizeIterator for IntoIter<Key, Value> {}
+
+impl<Key, Value> FusedIterator for IntoIter<Key, Value> {}
+
+impl<Key, Value> IntoIterator for HashMap<Key, Value> {
+  type Item = (Key, Value);
+  type IntoIter = IntoIter<Key, Value>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    IntoIter {
+      entries: self.entries,
+      occupancy: self.occupancy,
+      current_index: 0,
+      remaining: self.occupied,
+    }
+  }
+}
+
+impl<'a, Key, Value> IntoIterator for &'a HashMap<Key, Value> where Key: Hashable + PartialEq {
+  type Item = (&'a Key, &'a Value);
+  type IntoIter = HashMapIterator<'a, Key, Value>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, Key, Value> IntoIterator for &'a mut HashMap<Key, Value> where Key: Hashable + PartialEq {
+  type Item = (&'a Key, &'a mut Value);
+  type IntoIter = IterMut<'a, Key, Value>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+pub struct ValuesMut<'a, Key, Value> {
+  inner: IterMut<'a, Key, Value>,
+}
+
+impl<'a, Key, Value> Iterator for ValuesMut<'a, Key, Value> {
+  type Item = &'a mut Value;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
   }
 }
 
-impl<Key, Value> Default for HashMap<Key, Value> where Key: Clone + Default + Hashable + Debug + PartialEq, Value: Clone + Default + Debug {
+impl<Key, Value> ExactSizeIterator for ValuesMut<'_, Key, Value> {}
+
+impl<Key, Value> FusedIterator for ValuesMut<'_, Key, Value> {}
+
+impl<Key, Value> Default for HashMap<Key, Value> where Key: Hashable + PartialEq {
   fn default() -> Self {
     Self::new()
   }
 }
 
 #[allow(dead_code)]
-impl<Key, Value> HashMap<Key, Value> where Key: Clone + Default + Hashable + Debug + PartialEq, Value: Clone + Default + Debug {
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + PartialEq {
   pub fn new() -> Self {
     const INITIAL_CAPACITY: usize = 64;
     Self {
-      entries: vec![Entry::<_, _>::default(); INITIAL_CAPACITY],
+      entries: Slots::vacant(INITIAL_CAPACITY),
+      occupancy: Occupancy::with_slots(INITIAL_CAPACITY),
+      control: Control::with_slots(INITIAL_CAPACITY),
       occupied: 0,
+      max_load: DEFAULT_MAX_LOAD,
+      min_load: 0,
+      first_occupied: 0,
+      #[cfg(feature = "profiling")]
+      metrics: Default::default(),
     }
   }
 
   pub fn empty() -> Self {
     Self {
-      entries: vec![],
+      entries: Slots::default(),
+      occupancy: Occupancy::default(),
+      control: Control::default(),
       occupied: 0,
+      max_load: DEFAULT_MAX_LOAD,
+      min_load: 0,
+      first_occupied: 0,
+      #[cfg(feature = "profiling")]
+      metrics: Default::default(),
     }
   }
 
+  /// A map with `capacity` slots, of which only the load factor's share can be filled before
+  /// it grows; use `with_capacity_for` to size it by entries instead.
   pub fn with_capacity(capacity: usize) -> Self {
     Self {
-      entries: vec![Entry::<_, _>::default(); capacity],
+      entries: Slots::vacant(capacity),
+      occupancy: Occupancy::with_slots(capacity),
+      control: Control::with_slots(capacity),
       occupied: 0,
+      max_load: DEFAULT_MAX_LOAD,
+      min_load: 0,
+      first_occupied: 0,
+      #[cfg(feature = "profiling")]
+      metrics: Default::default(),
+    }
+  }
+
+  /// A map that holds `entries` entries without resizing.
+  pub fn with_capacity_for(entries: usize) -> Self {
+    let slots = Self::empty().slots_for(entries).expect("capacity overflow");
+    Self::with_capacity(slots)
+  }
+
+  /// An empty map that grows once more than `max_load_factor` of its slots are occupied.
+  ///
+  /// Panics unless `0 < max_load_factor < 1`.
+  pub fn with_load_factor(max_load_factor: f32) -> Self {
+    let mut map = Self::new();
+    map.set_max_load_factor(max_load_factor);
+    map
+  }
+
+  pub fn max_load_factor(&self) -> f32 {
+    self.max_load as f32 / MILLION as f32
+  }
+
+  /// Changes the load factor the table grows at, resizing right away if it is now over it.
+  ///
+  /// Panics unless `0 < max_load_factor < 1`; the table always keeps an empty slot so probes end.
+  pub fn set_max_load_factor(&mut self, max_load_factor: f32) {
+    assert!(max_load_factor > 0.0 && max_load_factor < 1.0, "max load factor must be between 0 and 1, got {}", max_load_factor);
+    self.max_load = (max_load_factor as f64 * MILLION as f64).round().clamp(1.0, (MILLION - 1) as f64) as u32;
+    self.reserve(0);
+  }
+
+  pub fn min_load_factor(&self) -> f32 {
+    self.min_load as f32 / MILLION as f32
+  }
+
+  /// Makes `remove`, `remove_entry` and `pop` shrink the table once fewer than
+  /// `min_load_factor` of its slots are occupied, e.g. 0.125 for maps whose contents come in
+  /// bursts. Zero, the default, never shrinks automatically.
+  ///
+  /// Panics unless `0 <= min_load_factor < max_load_factor / 2`, since a shrunk table is left
+  /// half as full as the maximum and must not be sparse right away.
+  pub fn set_min_load_factor(&mut self, min_load_factor: f32) {
+    assert!(
+      min_load_factor >= 0.0 && min_load_factor < self.max_load_factor() / 2.0,
+      "min load factor must be at least 0 and below half the max load factor, got {}",
+      min_load_factor
+    );
+    self.min_load = (min_load_factor as f64 * MILLION as f64).round() as u32;
+    self.shrink_if_sparse();
+  }
+
+  /// Shrinks the table if it is emptier than the minimum load factor allows, leaving it half
+  /// as full as the maximum so that a few inserts do not grow it right back.
+  fn shrink_if_sparse(&mut self) {
+    let min_entries = (self.entries.len() as u128 * self.min_load as u128 / MILLION as u128) as usize;
+    if self.occupied < min_entries {
+      self.shrink_to(self.occupied * 2);
+    }
+  }
+
+  /// Most entries a table of `slots` slots may hold under the load factor.
+  fn max_entries(&self, slots: usize) -> usize {
+    (slots as u128 * self.max_load as u128 / MILLION as u128) as usize
+  }
+
+  /// Fewest slots that hold `entries` entries under the load factor, or `None` on overflow.
+  fn slots_for(&self, entries: usize) -> Option<usize> {
+    usize::try_from((entries as u128 * MILLION as u128).div_ceil(self.max_load as u128)).ok()
+  }
+
+  /// Like `with_capacity`, but reports allocation failure instead of aborting.
+  pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+    // Each per-slot array is one allocation, and the widest one decides whether all of them fit.
+    let slot_bytes = size_of::<usize>().max(size_of::<Option<Key>>()).max(size_of::<Option<Value>>());
+    if capacity.checked_mul(slot_bytes).is_none_or(|bytes| bytes > isize::MAX as usize) {
+      return Err(TryReserveError::CapacityOverflow);
     }
+    Ok(Self {
+      entries: Slots::try_vacant(capacity).map_err(TryReserveError::AllocError)?,
+      occupancy: Occupancy::try_with_slots(capacity).map_err(TryReserveError::AllocError)?,
+      control: Control::try_with_slots(capacity).map_err(TryReserveError::AllocError)?,
+      occupied: 0,
+      max_load: DEFAULT_MAX_LOAD,
+      min_load: 0,
+      first_occupied: 0,
+      #[cfg(feature = "profiling")]
+      metrics: Default::default(),
+    })
   }
 
+  /// How many entries fit before the table grows; `bucket_count` is the number of slots.
   pub fn capacity(&self) -> usize {
-    self.entries.capacity()
+    self.max_entries(self.entries.len())
+  }
+
+  /// Number of slots in the table, occupied or not.
+  pub fn bucket_count(&self) -> usize {
+    self.entries.len()
   }
 
   pub fn len(&self) -> usize {
@@ -201,110 +729,2142 @@ impl<Key, Value> HashMap<Key, Value> where Key: Clone + Default + Hashable + Deb
     self.occupied == 0
   }
 
-  pub fn get_index(&self, key: &Key) -> Option<usize> {
-    let mut index = key.hash() % self.entries.len();
-    for _ in 0..self.entries.len() {
-      if !self.entries[index].occupied {
-        break;
-      }
-      if self.entries[index].key == *key {
-        break;
-      }
-      index = (index + 1) % self.entries.len();
+  /// Returns the slot holding `key`.
+  ///
+  /// Like every lookup, this accepts any borrowed form of the key (e.g. `&str` for `String`
+  /// keys); its `Hashable` and `PartialEq` impls must agree with those of `Key`.
+  pub fn get_index<Q: Hashable + PartialEq + ?Sized>(&self, key: &Q) -> Option<usize> where Key: Borrow<Q> {
+    self.find_slot(table_hash(key.hash()), key)
+  }
+
+  /// The entry in slot `index`, without checking that the slot exists and is occupied.
+  ///
+  /// # Safety
+  ///
+  /// `index` must be a slot returned by `get_index` with no modification of the map since.
+  pub unsafe fn get_unchecked(&self, index: usize) -> (&Key, &Value) {
+    // SAFETY: the caller guarantees `index` is an occupied slot, so it is in bounds of both
+    // arrays and both hold `Some`.
+    unsafe {
+      let key = self.entries.keys.get_unchecked(index).as_ref().unwrap_unchecked();
+      let value = self.entries.values.get_unchecked(index).as_ref().unwrap_unchecked();
+      (key, value)
     }
-    if self.entries[index].occupied && self.entries[index].key == *key {
-      Some(index)
-    } else {
-      None
+  }
+
+  /// Like `get_unchecked`, with mutable access to the value.
+  ///
+  /// # Safety
+  ///
+  /// `index` must be a slot returned by `get_index` with no modification of the map since.
+  pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> (&Key, &mut Value) {
+    // SAFETY: as in `get_unchecked`.
+    unsafe {
+      let key = self.entries.keys.get_unchecked(index).as_ref().unwrap_unchecked();
+      let value = self.entries.values.get_unchecked_mut(index).as_mut().unwrap_unchecked();
+      (key, value)
     }
   }
 
-  pub fn insert(&mut self, key: Key, new_value: Value) {
-    if let Some(old) = self.get_mut(&key) {
-      *old = new_value;
-    } else {
-      if self.occupied >= self.entries.len() / 2 {
-        self.extend();
-      }
-      let mut index = key.hash() % self.entries.len();
-      for _ in 0..self.entries.len() {
-        if !self.entries[index].occupied {
-          self.entries[index].occupied = true;
-          self.entries[index].key = key;
-          self.entries[index].value = new_value;
-          self.occupied += 1;
-          break;
+
+  /// Inserts `new_value` under `key`, returning the value it replaced, if any.
+  pub fn insert(&mut self, key: Key, new_value: Value) -> Option<Value> {
+    let hash = table_hash(key.hash());
+    if let Some(index) = self.find_slot(hash, &key) {
+      return Some(std::mem::replace(self.entries.value_mut(index), new_value));
+    }
+    self.insert_absent(hash, key, new_value);
+    None
+  }
+
+  /// Inserts an entry without first probing for an existing one, for loading data that is
+  /// already deduplicated.
+  ///
+  /// # Safety
+  ///
+  /// `key` must not be in the map. Breaking this does not corrupt memory, but leaves two
+  /// entries for the key, and which of them lookups and removals see is unspecified.
+  pub unsafe fn insert_unique_unchecked(&mut self, key: Key, value: Value) -> &mut Value {
+    let hash = table_hash(key.hash());
+    self.insert_absent(hash, key, value)
+  }
+
+  /// Inserts every entry of `items`, resizing up front for the iterator's lower size bound;
+  /// later entries for a key replace earlier ones, as with `insert`.
+  ///
+  /// The table is sized as if every key were new, so batches with many repeated keys leave it
+  /// sparser than inserting one by one would, while iterators that report no lower bound grow it
+  /// as `insert` does. Each key is hashed one entry ahead, letting each insert start loading the
+  /// home group of the next one.
+  pub fn insert_batch<I: IntoIterator<Item = (Key, Value)>>(&mut self, items: I) {
+    let items = items.into_iter();
+    self.reserve(items.size_hint().0);
+    let mut items = items.map(|(key, value)| (table_hash(key.hash()), key, value)).peekable();
+    while let Some((hash, key, value)) = items.next() {
+      // A table with no slots has no group to load; the insert below grows it.
+      if let Some(&(next, _, _)) = items.peek().filter(|_| !self.entries.is_empty()) {
+        self.control.prefetch(next % self.entries.len());
+      }
+      match self.find_slot(hash, &key) {
+        Some(index) => *self.entries.value_mut(index) = value,
+        None => {
+          self.insert_absent(hash, key, value);
         }
-        index = (index + 1) % self.entries.len();
       }
     }
   }
 
-  pub fn get(&self, key: &Key) -> Option<&Value> {
+  /// Stores an entry whose key is known to be absent, growing the table first if it is full.
+  ///
+  /// Growing only here, once a key turned out to be new, means lookups that find their key
+  /// never resize the table.
+  fn insert_absent(&mut self, hash: usize, key: Key, value: Value) -> &mut Value {
+    self.reserve_one();
+    let index = self.place(hash, key, value);
+    self.entries.value_mut(index)
+  }
+
+  /// Stores an entry whose key is known to be absent, without comparing keys, and returns the
+  /// slot it ended up in. The table must have at least one empty slot.
+  ///
+  /// Uses Robin Hood insertion: an entry that has probed further from its home than the one
+  /// in its way takes that slot, and the evicted entry continues probing in its place. This
+  /// keeps every cluster sorted by home slot and evens out probe lengths.
+  fn place(&mut self, hash: usize, key: Key, value: Value) -> usize {
+    let mut index = hash % self.entries.len();
+    let mut carried = (hash, key, value);
+    let mut distance = 0;
+    let mut placed = None;
+    loop {
+      self.record(|metrics| metrics.probes += 1);
+      if !self.entries.is_occupied(index) {
+        let (hash, key, value) = carried;
+        self.occupy(index, hash, key, value);
+        break;
+      }
+      let resident = self.displacement(index, self.entries.hashes[index]);
+      if resident < distance {
+        carried = self.entries.replace(index, carried).expect("slot is vacant");
+        self.control.set(index, self.entries.hashes[index]);
+        placed.get_or_insert(index);
+        distance = resident;
+      }
+      index = (index + 1) % self.entries.len();
+      distance += 1;
+    }
+    placed.unwrap_or(index)
+  }
+
+  /// How far the entry at `index` with the given hash sits from its home slot.
+  fn displacement(&self, index: usize, hash: usize) -> usize {
+    let len = self.entries.len();
+    (index + len - hash % len) % len
+  }
+
+  pub fn get<Q: Hashable + PartialEq + ?Sized>(&self, key: &Q) -> Option<&Value> where Key: Borrow<Q> {
     if let Some(index) = self.get_index(key) {
-      Some(&self.entries[index].value)
+      Some(self.entries.value(index))
     } else {
       None
     }
   }
 
-  pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+  /// Returns the stored key along with its value.
+  pub fn get_key_value<Q: Hashable + PartialEq + ?Sized>(&self, key: &Q) -> Option<(&Key, &Value)> where Key: Borrow<Q> {
+    self.entries.get(self.get_index(key)?)
+  }
+
+  pub fn contains_key<Q: Hashable + PartialEq + ?Sized>(&self, key: &Q) -> bool where Key: Borrow<Q> {
+    self.get_index(key).is_some()
+  }
+
+  pub fn get_mut<Q: Hashable + PartialEq + ?Sized>(&mut self, key: &Q) -> Option<&mut Value> where Key: Borrow<Q> {
     if let Some(index) = self.get_index(key) {
-      Some(&mut self.entries[index].value)
+      Some(self.entries.value_mut(index))
     } else {
       None
     }
   }
 
-  fn extend(&mut self) {
-    let mut new_self = Self::with_capacity(self.entries.len() * 2 + 1);
-    for entry in self.entries.iter() {
-      if entry.occupied {
-        new_self.insert(entry.key.clone(), entry.value.clone());
+  /// Applies `update` to the value of `key`, returning whether the key was present.
+  pub fn update<Q: Hashable + PartialEq + ?Sized, F: FnOnce(&mut Value)>(&mut self, key: &Q, update: F) -> bool where Key: Borrow<Q> {
+    match self.get_mut(key) {
+      Some(value) => {
+        update(value);
+        true
       }
+      None => false,
     }
-    *self = new_self;
   }
 
-  pub fn iter(&self) -> HashMapIterator<Key, Value> {
-    HashMapIterator {
-      entries: &self.entries,
-      current_index: 0,
-    }
+  /// Removes `key` and returns its value.
+  ///
+  /// Uses backward-shift deletion: entries after the freed slot that probed past it are moved
+  /// back, so no probe sequence is ever broken by the hole and no tombstones are needed.
+  pub fn remove<Q: Hashable + PartialEq + ?Sized>(&mut self, key: &Q) -> Option<Value> where Key: Borrow<Q> {
+    Some(self.remove_entry(key)?.1)
   }
 
-  #[cfg(debug_assertions)]
-  fn debug_dump(&self) {
-    for (index, entry) in self.entries.iter().enumerate() {
-      if entry.occupied {
-        println!("{}: {:?}", index, entry);
-      } else {
-        println!("{}: Empty", index)
+  /// Removes `key` and returns the stored key together with its value.
+  pub fn remove_entry<Q: Hashable + PartialEq + ?Sized>(&mut self, key: &Q) -> Option<(Key, Value)> where Key: Borrow<Q> {
+    let index = self.get_index(key)?;
+    let removed = self.remove_at(index);
+    self.shrink_if_sparse();
+    Some(removed)
+  }
+
+  /// Removes and returns an arbitrary entry, or `None` if the map is empty.
+  pub fn pop(&mut self) -> Option<(Key, Value)> {
+    let index = self.occupancy.next_from(self.first_occupied)?;
+    // Removal only shifts entries back into the freed slot, so nothing moves below `index`.
+    self.first_occupied = index;
+    let removed = self.remove_at(index);
+    self.shrink_if_sparse();
+    Some(removed)
+  }
+
+  /// Empties the occupied slot at `index` and closes the gap it leaves in the probe sequence.
+  fn remove_at(&mut self, mut hole: usize) -> (Key, Value) {
+    let removed = self.entries.take(hole).expect("slot is vacant");
+    self.occupancy.clear(hole);
+    self.control.clear(hole);
+    self.occupied -= 1;
+
+    // Clusters are sorted by home slot, so every following entry moves back by one until an
+    // empty slot or an entry that is already home.
+    loop {
+      let index = (hole + 1) % self.entries.len();
+      if !self.entries.is_occupied(index) || self.displacement(index, self.entries.hashes[index]) == 0 {
+        break;
       }
+      self.entries.swap(hole, index);
+      self.occupancy.set(hole);
+      self.occupancy.clear(index);
+      self.control.set(hole, self.entries.hashes[hole]);
+      self.control.clear(index);
+      hole = index;
     }
+    removed
   }
-}
 
-fn main() {
-  let mut days = HashMap::<u8, &str>::with_capacity(7);
-  days.insert(1, "Monday");
-  days.insert(2, "Tuesday");
-  days.insert(3, "Wednesday");
-  days.insert(4, "Thursday");
-  days.insert(5, "Friday");
-  days.insert(6, "Saturday");
-  days.insert(7, "Sunday");
-  days.debug_dump();
-  println!("{:?}", days.get(&1));
-  println!("{:?}", days.len());
-  for (key, value) in days.iter() {
-    println!("{}: {}", key, value);
+  /// Removes every entry, keeping the table's allocation for reuse.
+  pub fn clear(&mut self) {
+    let mut index = 0;
+    while let Some(occupied) = self.occupancy.next_from(index) {
+      self.entries.take(occupied);
+      index = occupied + 1;
+    }
+    self.occupancy.clear_all();
+    self.control.clear_all();
+    self.occupied = 0;
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+  /// Lazily removes and yields the entries for which `predicate` returns true.
+  ///
+  /// Entries not yet visited when the iterator is dropped stay in the map.
+  pub fn extract_if<F: FnMut(&Key, &mut Value) -> bool>(&mut self, predicate: F) -> ExtractIf<'_, Key, Value, F> {
+    // Starting right after an empty slot means no probe chain wraps past the start, so the
+    // backward shifts done by removals only ever pull in entries that have not been visited.
+    let start = self.entries.keys.iter().position(Option::is_none).map_or(0, |empty| empty + 1);
+    ExtractIf {
+      map: self,
+      predicate,
+      start,
+      visited: 0,
+    }
+  }
+
+  /// A cursor over the entries that can replace or remove them while walking the table.
+  pub fn cursor_mut(&mut self) -> CursorMut<'_, Key, Value> {
+    // Starts after an empty slot for the same reason as `extract_if`.
+    let start = self.entries.keys.iter().position(Option::is_none).map_or(0, |empty| empty + 1);
+    CursorMut {
+      map: self,
+      start,
+      visited: 0,
+      current: None,
+    }
+  }
+
+  /// Moves the entries matching `predicate` into a new map, keeping the rest.
+  pub fn split_off<F: FnMut(&Key, &Value) -> bool>(&mut self, mut predicate: F) -> Self {
+    let mut split = self.empty_like();
+    split.extend(self.extract_if(|key, value| predicate(key, value)));
+    split
+  }
+
+  /// A map without slots that grows and shrinks at the same load factors as `self`.
+  fn empty_like(&self) -> Self {
+    let mut map = Self::empty();
+    map.max_load = self.max_load;
+    map.min_load = self.min_load;
+    map
+  }
+
+  /// Moves the entries of `keys` that are present into a new map, keeping the rest.
+  pub fn split_off_keys<'k, I: IntoIterator<Item = &'k Key>>(&mut self, keys: I) -> Self where Key: 'k {
+    let mut split = self.empty_like();
+    for key in keys {
+      if let Some(index) = self.get_index(key) {
+        let (key, value) = self.remove_at(index);
+        split.insert(key, value);
+      }
+    }
+    split
+  }
+
+  /// Moves every entry of `other` into `self`, replacing values of keys present in both.
+  /// `other` is left empty but keeps its allocation.
+  pub fn append(&mut self, other: &mut Self) {
+    self.reserve(other.len());
+    let mut index = 0;
+    while let Some(occupied) = other.occupancy.next_from(index) {
+      let (key, value) = other.entries.take(occupied).expect("slot is vacant");
+      // The cached hash saves hashing every key again.
+      let hash = other.entries.hashes[occupied];
+      match self.find_slot(hash, &key) {
+        Some(existing) => *self.entries.value_mut(existing) = value,
+        None => {
+          self.insert_absent(hash, key, value);
+        }
+      }
+      index = occupied + 1;
+    }
+    other.occupancy.clear_all();
+    other.control.clear_all();
+    other.occupied = 0;
+  }
+
+  /// Gets the entry for `key` for in-place manipulation with a single probe.
+  pub fn entry(&mut self, key: Key) -> Entry<'_, Key, Value> {
+    let hash = table_hash(key.hash());
+    match self.find_slot(hash, &key) {
+      Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+      None => Entry::Vacant(VacantEntry { map: self, key, hash }),
+    }
+  }
+
+  /// Like `entry`, but takes a borrowed key and only builds an owned `Key` on insertion.
+  pub fn entry_ref<'q, Q: Hashable + PartialEq + ?Sized>(&mut self, key: &'q Q) -> EntryRef<'_, 'q, Key, Q, Value> where Key: Borrow<Q> {
+    let hash = table_hash(key.hash());
+    match self.find_slot(hash, key) {
+      Some(index) => EntryRef::Occupied(OccupiedEntry { map: self, index }),
+      None => EntryRef::Vacant(VacantEntryRef { map: self, key, hash }),
+    }
+  }
+
+  /// Lookups by a hash the caller has already computed, skipping `Hashable::hash`.
+  pub fn raw_entry(&self) -> RawEntryBuilder<'_, Key, Value> {
+    RawEntryBuilder { map: self }
+  }
+
+  /// Lookups and insertions by a hash the caller has already computed.
+  pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, Key, Value> {
+    RawEntryBuilderMut { map: self }
+  }
+
+  /// Mutable references to the values of `N` distinct keys at once.
+  ///
+  /// Returns `None` if any key is missing or the same key is passed twice.
+  pub fn get_many_mut<const N: usize>(&mut self, keys: [&Key; N]) -> Option<[&mut Value; N]> {
+    let mut indices = [0; N];
+    for (index, key) in indices.iter_mut().zip(keys) {
+      *index = self.get_index(key)?;
+    }
+    let values = self.entries.values.get_disjoint_mut(indices).ok()?;
+    Some(values.map(|value| value.as_mut().expect("slot is vacant")))
+  }
+
+  /// Returns the value of `key`, inserting `default()` first if it is missing, in a single probe.
+  pub fn get_or_insert_with<F: FnOnce() -> Value>(&mut self, key: Key, default: F) -> &mut Value {
+    self.entry(key).or_insert_with(default)
+  }
+
+  /// Inserts `value` only if `key` is absent; otherwise leaves the map untouched and hands the
+  /// existing entry and the rejected value back in the error.
+  pub fn try_insert(&mut self, key: Key, value: Value) -> Result<&mut Value, OccupiedError<'_, Key, Value>> {
+    match self.entry(key) {
+      Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+      Entry::Vacant(entry) => Ok(entry.insert(value)),
+    }
+  }
+
+  /// Probes for `key`, whose hash is `hash`, and returns the index of its slot.
+  fn find_slot<Q: Hashable + PartialEq + ?Sized>(&self, hash: usize, key: &Q) -> Option<usize> where Key: Borrow<Q> {
+    self.find_slot_by(hash, |stored| stored.borrow() == key)
+  }
+
+  /// `find_slot` matching stored keys with `is_match`.
+  ///
+  /// Scans the control bytes a group of slots at a time, up to the first empty slot, and only
+  /// looks at slots whose tag matches `hash`; of those, slots whose cached hash differs are
+  /// skipped without calling `is_match`.
+  fn find_slot_by<F: FnMut(&Key) -> bool>(&self, hash: usize, mut is_match: F) -> Option<usize> {
+    if self.entries.is_empty() {
+      return None;
+    }
+    let len = self.entries.len();
+    let tag = Control::tag(hash);
+    let mut start = hash % len;
+    loop {
+      let group = self.control.group(start, tag);
+      // Only slots before the first empty one belong to the probe sequence.
+      let end = (group.empties.trailing_zeros() as usize).min(control::GROUP);
+      let next_start = (start + control::GROUP) % len;
+      if end == control::GROUP {
+        // The probe continues into the next group; start loading it while this one is checked.
+        self.control.prefetch(next_start);
+      }
+      let mut candidates = group.matches as u32 & ((1 << end) - 1);
+      while candidates != 0 {
+        let offset = candidates.trailing_zeros() as usize;
+        let index = (start + offset) % len;
+        let rest = candidates & (candidates - 1);
+        if rest != 0 {
+          self.entries.prefetch((start + rest.trailing_zeros() as usize) % len);
+        }
+        if self.entries.hashes[index] == hash {
+          self.record(|metrics| metrics.comparisons += 1);
+          if is_match(self.entries.key(index)) {
+            self.record(|metrics| metrics.probes += offset as u64 + 1);
+            return Some(index);
+          }
+        }
+        candidates = rest;
+      }
+      if end < control::GROUP {
+        self.record(|metrics| metrics.probes += end as u64 + 1);
+        return None;
+      }
+      self.record(|metrics| metrics.probes += control::GROUP as u64);
+      start = next_start;
+    }
+  }
+
+  /// Stores a new entry in the empty slot at `index`; see `place` for choosing the slot.
+  fn occupy(&mut self, index: usize, hash: usize, key: Key, value: Value) -> &mut Value {
+    self.occupancy.set(index);
+    self.first_occupied = self.first_occupied.min(index);
+    self.control.set(index, hash);
+    self.occupied += 1;
+    self.entries.hashes[index] = hash;
+    self.entries.keys[index] = Some(key);
+    self.entries.values[index].insert(value)
+  }
+
+  /// Grows the table if one more entry would exceed the load factor.
+  fn reserve_one(&mut self) {
+    if self.occupied >= self.max_entries(self.entries.len()) {
+      self.grow();
+    }
+  }
+
+  /// Grows the table once so that `additional` more entries fit without further resizing.
+  pub fn reserve(&mut self, additional: usize) {
+    let slots = self.occupied.checked_add(additional).and_then(|entries| self.slots_for(entries)).expect("capacity overflow");
+    if slots > self.entries.len() {
+      self.resize(slots);
+    }
+  }
+
+  /// Inserts `value`, or folds it into the existing value with `reduce`, in a single probe.
+  pub fn insert_or_reduce<F: FnOnce(&mut Value, Value)>(&mut self, key: Key, value: Value, reduce: F) {
+    let hash = table_hash(key.hash());
+    match self.find_slot(hash, &key) {
+      Some(index) => reduce(self.entries.value_mut(index), value),
+      None => {
+        self.insert_absent(hash, key, value);
+      }
+    }
+  }
+
+  /// Groups `items` by `key_fn`, folding each group into an accumulator that starts as `init()`.
+  pub fn aggregate<Item, I, K, N, F>(items: I, key_fn: K, init: N, fold: F) -> Self
+  where
+    I: IntoIterator<Item = Item>,
+    K: Fn(&Item) -> Key,
+    N: Fn() -> Value,
+    F: Fn(&mut Value, Item),
+  {
+    let mut map = Self::new();
+    for item in items {
+      let key = key_fn(&item);
+      let hash = table_hash(key.hash());
+      let accumulator = match map.find_slot(hash, &key) {
+        Some(index) => map.entries.value_mut(index),
+        None => map.insert_absent(hash, key, init()),
+      };
+      fold(accumulator, item);
+    }
+    map
+  }
+
+  /// Shrinks the table as much as the load factor allows.
+  pub fn shrink_to_fit(&mut self) {
+    self.shrink_to(0);
+  }
+
+  /// Shrinks the table while keeping room for at least `min_capacity` entries, and never below
+  /// what the current entries need under the load factor.
+  pub fn shrink_to(&mut self, min_capacity: usize) {
+    let slots = self.slots_for(min_capacity.max(self.occupied)).expect("capacity overflow");
+    if slots < self.entries.len() {
+      self.resize(slots);
+    }
+  }
+
+  fn grow(&mut self) {
+    // Tiny tables, such as one shrunk down to nothing, would still be full after doubling.
+    let needed = self.slots_for(self.occupied + 1).expect("capacity overflow");
+    self.resize((self.entries.len() * 2 + 1).max(needed));
+  }
+
+  /// Rehashes every entry into a new table of `slots` slots.
+  fn resize(&mut self, slots: usize) {
+    self.rehash_into(Self::with_capacity(slots));
+  }
+
+  /// Like `reserve`, but reports allocation failure instead of aborting.
+  pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+    let slots = self.occupied
+      .checked_add(additional)
+      .and_then(|entries| self.slots_for(entries))
+      .ok_or(TryReserveError::CapacityOverflow)?;
+    if slots > self.entries.len() {
+      self.rehash_into(Self::try_with_capacity(slots)?);
+    }
+    Ok(())
+  }
+
+  /// Moves every entry into `new_self`, which must be large enough to hold them, and replaces `self` with it.
+  ///
+  /// Keys are already distinct, so entries are placed without looking for an existing match.
+  fn rehash_into(&mut self, mut new_self: Self) {
+    new_self.max_load = self.max_load;
+    new_self.min_load = self.min_load;
+    for (hash, key, value) in std::mem::take(&mut self.entries).into_entries() {
+      new_self.place(hash, key, value);
+    }
+    #[cfg(feature = "profiling")]
+    new_self.metrics.store(self.metrics() + new_self.metrics() + Metrics { resizes: 1, ..Default::default() });
+    *self = new_self;
+  }
+
+  pub fn iter(&self) -> HashMapIterator<'_, Key, Value> {
+    HashMapIterator {
+      entries: &self.entries,
+      occupancy: &self.occupancy,
+      current_index: 0,
+      remaining: self.occupied,
+    }
+  }
+
+  pub fn iter_mut(&mut self) -> IterMut<'_, Key, Value> {
+    IterMut {
+      keys: self.entries.keys.iter(),
+      values: self.entries.values.iter_mut(),
+      occupancy: &self.occupancy,
+      current_index: 0,
+      remaining: self.occupied,
+    }
+  }
+
+  pub fn keys(&self) -> Keys<'_, Key, Value> {
+    Keys { inner: self.iter() }
+  }
+
+  pub fn values(&self) -> Values<'_, Key, Value> {
+    Values { inner: self.iter() }
+  }
+
+  pub fn values_mut(&mut self) -> ValuesMut<'_, Key, Value> {
+    ValuesMut { inner: self.iter_mut() }
+  }
+
+  /// Entry with the greatest `key_fn(key, value)`; the last one wins on ties.
+  pub fn max_by_key_fn<T: Ord, F: Fn(&Key, &Value) -> T>(&self, key_fn: F) -> Option<(&Key, &Value)> {
+    self.iter().max_by_key(|(key, value)| key_fn(key, value))
+  }
+
+  /// Entry with the smallest `key_fn(key, value)`; the first one wins on ties.
+  pub fn min_by_key_fn<T: Ord, F: Fn(&Key, &Value) -> T>(&self, key_fn: F) -> Option<(&Key, &Value)> {
+    self.iter().min_by_key(|(key, value)| key_fn(key, value))
+  }
+
+  /// Describes every slot of the table: its state and, for occupied slots, where the key probed from.
+  pub fn dump(&self) -> DumpReport<'_, Key> {
+    let slots = self.entries.keys.iter().enumerate().map(|(index, key)| {
+      if let Some(key) = key {
+        let home = self.entries.hashes[index] % self.entries.len();
+        DumpSlot {
+          index,
+          state: SlotState::Occupied,
+          key: Some(key),
+          home: Some(home),
+          displacement: Some((index + self.entries.len() - home) % self.entries.len()),
+        }
+      } else {
+        DumpSlot {
+          index,
+          state: SlotState::Empty,
+          key: None,
+          home: None,
+          displacement: None,
+        }
+      }
+    }).collect();
+    DumpReport { slots }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotState {
+  Empty,
+  Occupied,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DumpSlot<'a, Key> {
+  pub index: usize,
+  pub state: SlotState,
+  pub key: Option<&'a Key>,
+  /// Bucket the key hashes to.
+  pub home: Option<usize>,
+  /// Distance probed from `home` to reach `index`.
+  pub displacement: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DumpReport<'a, Key> {
+  pub slots: Vec<DumpSlot<'a, Key>>,
+}
+
+impl<Key> Display for DumpReport<'_, Key> where Key: Debug {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for slot in self.slots.iter() {
+      match (slot.key, slot.home, slot.displacement) {
+        (Some(key), Some(home), Some(displacement)) => {
+          writeln!(f, "{}: {:?} home={} displacement={}", slot.index, key, home, displacement)?;
+        }
+        _ => writeln!(f, "{}: Empty", slot.index)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+pub struct ExtractIf<'a, Key, Value, F> {
+  map: &'a mut HashMap<Key, Value>,
+  predicate: F,
+  start: usize,
+  visited: usize,
+}
+
+impl<Key, Value, F> Iterator for ExtractIf<'_, Key, Value, F> where Key: Hashable + PartialEq, F: FnMut(&Key, &mut Value) -> bool {
+  type Item = (Key, Value);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let len = self.map.entries.len();
+    while self.visited < len {
+      let index = (self.start + self.visited) % len;
+      let matches = match self.map.entries.get_mut(index) {
+        Some((key, value)) => (self.predicate)(key, value),
+        None => false,
+      };
+      if matches {
+        // Removal may shift a later entry into `index`, so it is visited again.
+        return Some(self.map.remove_at(index));
+      }
+      self.visited += 1;
+    }
+    None
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (0, Some(self.map.len()))
+  }
+}
+
+impl<Key, Value, F> FusedIterator for ExtractIf<'_, Key, Value, F> where Key: Hashable + PartialEq, F: FnMut(&Key, &mut Value) -> bool {}
+
+impl<Key, Value> Extend<(Key, Value)> for HashMap<Key, Value> where Key: Hashable + PartialEq {
+  fn extend<T: IntoIterator<Item = (Key, Value)>>(&mut self, items: T) {
+    let items = items.into_iter();
+    // Existing keys may be repeated in `items`, so only reserve for half of them unless empty.
+    let hint = items.size_hint().0;
+    self.reserve(if self.is_empty() { hint } else { hint.div_ceil(2) });
+    for (key, value) in items {
+      self.insert(key, value);
+    }
+  }
+}
+
+impl<'a, Key, Value> Extend<(&'a Key, &'a Value)> for HashMap<Key, Value> where Key: Copy + Hashable + PartialEq, Value: Copy {
+  fn extend<T: IntoIterator<Item = (&'a Key, &'a Value)>>(&mut self, items: T) {
+    self.extend(items.into_iter().map(|(key, value)| (*key, *value)));
+  }
+}
+
+impl<Key, Value> FromIterator<(Key, Value)> for HashMap<Key, Value> where Key: Hashable + PartialEq {
+  fn from_iter<T: IntoIterator<Item = (Key, Value)>>(items: T) -> Self {
+    let mut map = HashMap::new();
+    map.extend(items);
+    map
+  }
+}
+
+impl<Key, Value, const N: usize> From<[(Key, Value); N]> for HashMap<Key, Value> where Key: Hashable + PartialEq {
+  fn from(entries: [(Key, Value); N]) -> Self {
+    entries.into_iter().collect()
+  }
+}
+
+impl<Key, Value> std::ops::Index<&Key> for HashMap<Key, Value> where Key: Hashable + PartialEq {
+  type Output = Value;
+
+  /// Panics if `key` is not in the map.
+  fn index(&self, key: &Key) -> &Value {
+    match self.get(key) {
+      Some(value) => value,
+      None => panic!("key not found in HashMap"),
+    }
+  }
+}
+
+impl<Key, Value> std::ops::IndexMut<&Key> for HashMap<Key, Value> where Key: Hashable + PartialEq {
+  /// Panics if `key` is not in the map; use `entry` to insert missing keys.
+  fn index_mut(&mut self, key: &Key) -> &mut Value {
+    match self.get_index(key) {
+      Some(index) => self.entries.value_mut(index),
+      None => panic!("key not found in HashMap"),
+    }
+  }
+}
+
+impl<Key, Value> Clone for HashMap<Key, Value> where Key: Clone, Value: Clone {
+  fn clone(&self) -> Self {
+    Self {
+      entries: self.entries.clone(),
+      occupancy: self.occupancy.clone(),
+      control: self.control.clone(),
+      occupied: self.occupied,
+      max_load: self.max_load,
+      min_load: self.min_load,
+      first_occupied: self.first_occupied,
+      #[cfg(feature = "profiling")]
+      metrics: self.metrics.clone(),
+    }
+  }
+
+  /// Copies `source` into the table's existing allocations, growing them only if `source` is
+  /// larger; keys and values that are already there are overwritten with `Clone::clone_from`.
+  fn clone_from(&mut self, source: &Self) {
+    self.entries.clone_from(&source.entries);
+    self.occupancy.clone_from(&source.occupancy);
+    self.control.clone_from(&source.control);
+    self.occupied = source.occupied;
+    self.max_load = source.max_load;
+    self.min_load = source.min_load;
+    self.first_occupied = source.first_occupied;
+    #[cfg(feature = "profiling")]
+    self.metrics.clone_from(&source.metrics);
+  }
+}
+
+/// Maps are equal when they hold the same entries, whatever their capacity or slot layout.
+impl<Key, Value> PartialEq for HashMap<Key, Value> where Key: Hashable + PartialEq, Value: PartialEq {
+  fn eq(&self, other: &Self) -> bool {
+    self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+  }
+}
+
+impl<Key, Value> Eq for HashMap<Key, Value> where Key: Hashable + Eq, Value: Eq {}
+
+/// Prints only the stored entries, like `{1: "a", 2: "b"}`; use `dump` to see the slots.
+impl<Key, Value> Debug for HashMap<Key, Value> where Key: Debug, Value: Debug {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let entries = HashMapIterator {
+      entries: &self.entries,
+      occupancy: &self.occupancy,
+      current_index: 0,
+      remaining: self.occupied,
+    };
+    f.debug_map().entries(entries).finish()
+  }
+}
+
+pub struct Pretty<'a, Key, Value> {
+  map: &'a HashMap<Key, Value>,
+  limit: Option<usize>,
+}
+
+impl<'a, Key, Value> Pretty<'a, Key, Value> {
+  /// Prints at most `limit` entries followed by a count of the omitted ones.
+  pub fn limit(mut self, limit: usize) -> Self {
+    self.limit = Some(limit);
+    self
+  }
+}
+
+impl<Key, Value> Display for Pretty<'_, Key, Value> where Key: Hashable + PartialEq + Ord + Display, Value: Display {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut entries: Vec<(&Key, &Value)> = self.map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    let shown = self.limit.unwrap_or(entries.len()).min(entries.len());
+    let keys: Vec<String> = entries[..shown].iter().map(|(key, _)| key.to_string()).collect();
+    let width = keys.iter().map(|key| key.chars().count()).max().unwrap_or(0);
+    writeln!(f, "{{")?;
+    for (key, (_, value)) in keys.iter().zip(entries.iter()) {
+      writeln!(f, "  {:<width$} : {}", key, value, width = width)?;
+    }
+    if shown < entries.len() {
+      writeln!(f, "  ... ({} more)", entries.len() - shown)?;
+    }
+    write!(f, "}}")
+  }
+}
+
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + PartialEq + Ord + Display, Value: Display {
+  /// Returns a `Display` adapter printing the entries sorted by key, with keys aligned.
+  pub fn pretty(&self) -> Pretty<'_, Key, Value> {
+    Pretty {
+      map: self,
+      limit: None,
+    }
+  }
+}
+
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + PartialEq + Eq + std::hash::Hash {
+  pub fn keys_set(&self) -> std::collections::HashSet<&Key> {
+    self.keys().collect()
+  }
+
+  /// Keys present in either map.
+  pub fn keys_union<'a, Other>(&'a self, other: &'a HashMap<Key, Other>) -> std::collections::HashSet<&'a Key> {
+    self.keys().chain(other.keys()).collect()
+  }
+
+  /// Keys present in both maps.
+  pub fn keys_intersection<'a, Other>(&'a self, other: &HashMap<Key, Other>) -> std::collections::HashSet<&'a Key> {
+    self.keys().filter(|key| other.contains_key(key)).collect()
+  }
+}
+
+impl<Key, Value> HashMap<Key, Value> where Key: Clone + Hashable + PartialEq, Value: Clone + Hashable + PartialEq {
+  /// Builds the reverse map. When several keys share a value, which one is kept is unspecified;
+  /// use `invert_grouped` to keep all of them.
+  pub fn invert(&self) -> HashMap<Value, Key> {
+    let mut inverted = HashMap::with_capacity(self.entries.len());
+    for (key, value) in self.iter() {
+      inverted.insert(value.clone(), key.clone());
+    }
+    inverted
+  }
+
+  pub fn invert_grouped(&self) -> HashMap<Value, Vec<Key>> {
+    let mut inverted = HashMap::<Value, Vec<Key>>::with_capacity(self.entries.len());
+    for (key, value) in self.iter() {
+      if let Some(keys) = inverted.get_mut(value) {
+        keys.push(key.clone());
+      } else {
+        inverted.insert(value.clone(), vec![key.clone()]);
+      }
+    }
+    inverted
+  }
+}
+
+impl<Value> HashMap<String, Value> {
+  /// Entries whose key starts with `prefix`. This is a full scan of the table.
+  pub fn iter_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a String, &'a Value)> + 'a {
+    self.iter().filter(move |(key, _)| key.starts_with(prefix))
+  }
+}
+
+impl<'k, Value> HashMap<&'k str, Value> {
+  /// Entries whose key starts with `prefix`. This is a full scan of the table.
+  pub fn iter_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a &'k str, &'a Value)> + 'a {
+    self.iter().filter(move |(key, _)| key.starts_with(prefix))
+  }
+}
+
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + PartialEq, Value: Ord {
+  pub fn max_by_value(&self) -> Option<(&Key, &Value)> {
+    self.iter().max_by(|a, b| a.1.cmp(b.1))
+  }
+
+  pub fn min_by_value(&self) -> Option<(&Key, &Value)> {
+    self.iter().min_by(|a, b| a.1.cmp(b.1))
+  }
+}
+
+fn main() -> std::io::Result<()> {
+  use std::io::IsTerminal;
+  let stdin = std::io::stdin();
+  let prompt = stdin.is_terminal();
+  repl::run(stdin.lock(), std::io::stdout(), prompt)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Integer keys whose home in a table of `slots` slots is `home`.
+  fn keys_homed_at(home: usize, slots: usize) -> impl Iterator<Item = usize> {
+    (0..).filter(move |&key| table_hash(key) % slots == home)
+  }
+
+  #[test]
+  fn pretty_sorts_and_truncates() {
+    let mut map = HashMap::<u32, &str>::new();
+    map.insert(10, "ten");
+    map.insert(2, "two");
+    map.insert(7, "seven");
+    assert_eq!(map.pretty().to_string(), "{\n  2  : two\n  7  : seven\n  10 : ten\n}");
+    assert_eq!(map.pretty().limit(1).to_string(), "{\n  2 : two\n  ... (2 more)\n}");
+  }
+
+  #[test]
+  fn dump_reports_displacement() {
+    let mut map = HashMap::<u32, u32>::with_capacity(8);
+    // 1 and 11 both hash to slot 5 of 8.
+    map.insert(1, 10);
+    map.insert(11, 110);
+    let report = map.dump();
+    assert_eq!(report.slots.len(), 8);
+    assert_eq!(report.slots[5].key, Some(&1));
+    assert_eq!(report.slots[6].key, Some(&11));
+    assert_eq!(report.slots[6].home, Some(5));
+    assert_eq!(report.slots[6].displacement, Some(1));
+    assert_eq!(report.slots[0].state, SlotState::Empty);
+    assert!(report.to_string().contains("6: 11 home=5 displacement=1"));
+  }
+
+  #[cfg(feature = "visualize")]
+  #[test]
+  fn visualize_draws_every_slot() {
+    let mut map = HashMap::<u32, u32>::with_capacity(8);
+    map.insert(1, 10);
+    map.insert(11, 110);
+    let svg = map.visualize();
+    assert_eq!(svg.matches("<rect").count(), 8);
+    assert_eq!(svg.matches("<line").count(), 1);
+  }
+
+  #[test]
+  fn min_load_factor_shrinks_after_removals() {
+    let mut map: HashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    map.set_min_load_factor(0.125);
+    let peak = map.bucket_count();
+    for i in 0..999 {
+      map.remove(&i);
+      assert!(map.len() * 8 >= map.bucket_count() / 2, "{} entries in {} slots", map.len(), map.bucket_count());
+    }
+    assert!(map.bucket_count() < peak / 100);
+    assert_eq!(map.pop(), Some((999, 999)));
+    // Growing a table without slots must still leave one empty so probes end.
+    let mut map = HashMap::<u32, u32>::empty();
+    map.insert(1, 1);
+    assert_eq!((map.get(&1), map.get(&2)), (Some(&1), None));
+  }
+
+  #[cfg(feature = "profiling")]
+  #[test]
+  fn metrics_count_probes_and_resizes() {
+    let mut map = HashMap::<u32, u32>::with_capacity(4);
+    map.insert(3, 3);
+    map.insert(4, 4);
+    map.reset_metrics();
+    assert_eq!(map.get(&4), Some(&4));
+    // Key 3 shares the home slot of 4 but not its tag, so it is skipped without comparing.
+    assert_eq!(map.metrics(), Metrics { probes: 2, comparisons: 1, resizes: 0 });
+    map.insert(2, 2);
+    assert_eq!(map.metrics().resizes, 1);
+  }
+
+  #[test]
+  fn maps_are_send_and_sync_with_any_features() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<HashMap<u32, String>>();
+  }
+
+  #[cfg(feature = "differential")]
+  #[test]
+  fn differential_against_std() {
+    for seed in 0..16 {
+      let mut map = HashMap::<u64, u64>::with_capacity(1);
+      if let Err(divergence) = differential::run(&mut map, seed, 2_000, 256) {
+        panic!("seed {}: {}", seed, divergence);
+      }
+    }
+  }
+
+  #[cfg(feature = "differential")]
+  #[test]
+  fn differential_reports_final_contents_without_an_op() {
+    /// Answers every operation correctly but forgets its entries when asked to list them.
+    struct Forgetful(HashMap<u64, u64>);
+    impl differential::Backend for Forgetful {
+      fn insert(&mut self, key: u64, value: u64) -> Option<u64> {
+        self.0.insert(key, value)
+      }
+      fn get(&self, key: &u64) -> Option<u64> {
+        self.0.get(key).copied()
+      }
+      fn remove(&mut self, key: &u64) -> Option<u64> {
+        self.0.remove(key)
+      }
+      fn len(&self) -> usize {
+        self.0.len()
+      }
+      fn entries(&self) -> Vec<(u64, u64)> {
+        Vec::new()
+      }
+    }
+
+    let divergence = differential::run(&mut Forgetful(HashMap::new()), 0, 100, 16).unwrap_err();
+    assert_eq!((divergence.step, divergence.op), (100, None));
+    assert!(divergence.to_string().starts_with("after 100 steps: final entries"));
+  }
+
+  #[cfg(feature = "differential")]
+  #[test]
+  fn differential_cuckoo_against_std() {
+    for seed in 0..16 {
+      let mut map = CuckooMap::<u64, u64>::with_capacity(2);
+      if let Err(divergence) = differential::run(&mut map, seed, 2_000, 256) {
+        panic!("seed {}: {}", seed, divergence);
+      }
+    }
+  }
+
+  #[cfg(feature = "differential")]
+  #[test]
+  fn differential_hopscotch_against_std() {
+    for seed in 0..16 {
+      let mut map = HopscotchMap::<u64, u64>::with_capacity(1);
+      if let Err(divergence) = differential::run(&mut map, seed, 2_000, 256) {
+        panic!("seed {}: {}", seed, divergence);
+      }
+    }
+  }
+
+  #[cfg(feature = "differential")]
+  #[test]
+  fn differential_small_map_against_std() {
+    // Eight keys never spill an eight-entry map; 256 spill it early in every run.
+    for (seed, key_range) in (0..16).flat_map(|seed| [(seed, 8), (seed, 256)]) {
+      let mut map = SmallMap::<u64, u64, 8>::new();
+      if let Err(divergence) = differential::run(&mut map, seed, 2_000, key_range) {
+        panic!("seed {} over {} keys: {}", seed, key_range, divergence);
+      }
+      assert_eq!(map.is_inline(), key_range == 8);
+    }
+  }
+
+  #[test]
+  fn key_set_algebra() {
+    let mut left = HashMap::<u32, &str>::new();
+    left.insert(1, "a");
+    left.insert(2, "b");
+    let mut right = HashMap::<u32, bool>::new();
+    right.insert(2, true);
+    right.insert(3, false);
+    assert_eq!(left.keys_set(), [&1, &2].into_iter().collect());
+    assert_eq!(left.keys_union(&right), [&1, &2, &3].into_iter().collect());
+    assert_eq!(left.keys_intersection(&right), [&2].into_iter().collect());
+  }
+
+  #[test]
+  fn invert_grouped_collects_all_keys() {
+    let mut map = HashMap::<u32, char>::new();
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.insert(3, 'a');
+    assert_eq!(map.invert().get(&'b'), Some(&2));
+    let mut keys = map.invert_grouped().get(&'a').cloned().unwrap();
+    keys.sort();
+    assert_eq!(keys, vec![1, 3]);
+  }
+
+  #[cfg(feature = "rand")]
+  #[test]
+  fn random_entry_and_sample_return_live_entries() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut map = HashMap::<u32, u32>::with_capacity(1024);
+    assert_eq!(map.random_entry(&mut rng), None);
+    for i in 0..10 {
+      map.insert(i, i * 10);
+    }
+    for _ in 0..100 {
+      let (key, value) = map.random_entry(&mut rng).unwrap();
+      assert_eq!(*value, key * 10);
+    }
+    let mut sample: Vec<u32> = map.sample(4, &mut rng).into_iter().map(|(key, _)| *key).collect();
+    sample.sort();
+    sample.dedup();
+    assert_eq!(sample.len(), 4);
+    assert_eq!(map.sample(50, &mut rng).len(), 10);
+  }
+
+  #[cfg(feature = "rand")]
+  #[test]
+  fn sample_iter_is_roughly_uniform() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+    let mut map = HashMap::<u32, u32>::new();
+    for i in 0..10 {
+      map.insert(i, i);
+    }
+    let mut hits = [0usize; 10];
+    for _ in 0..10_000 {
+      for (key, _) in map.sample_iter(3, &mut rng) {
+        hits[*key as usize] += 1;
+      }
+    }
+    assert!(hits.iter().all(|&count| (2_700..3_300).contains(&count)), "{:?}", hits);
+  }
+
+  #[cfg(feature = "rand")]
+  #[test]
+  fn weighted_sampling_follows_weights() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    let mut map = HashMap::<char, f64>::new();
+    map.insert('a', 1.0);
+    map.insert('b', 3.0);
+    map.insert('z', 0.0);
+    let sampler = map.weighted_sampler(|weight| *weight);
+    let mut hits = HashMap::<char, usize>::new();
+    for _ in 0..8_000 {
+      let key = *sampler.sample(&mut rng).unwrap();
+      let count = hits.get(&key).copied().unwrap_or(0);
+      hits.insert(key, count + 1);
+    }
+    assert_eq!(hits.get(&'z'), None);
+    assert!((1_800..2_200).contains(hits.get(&'a').unwrap()));
+    assert_ne!(map.sample_weighted(&mut rng, |weight| *weight), Some(&'z'));
+    assert_eq!(map.sample_weighted(&mut rng, |_| 0.0), None);
+  }
+
+  #[test]
+  fn iter_skips_sparse_regions() {
+    let mut map = HashMap::<usize, usize>::with_capacity(1000);
+    // Entries on either side of 64-slot word boundaries, with long empty runs between them.
+    let mut expected: Vec<usize> = [0, 63, 64, 130, 999].iter().map(|&home| keys_homed_at(home, 1000).next().unwrap()).collect();
+    for &key in &expected {
+      map.insert(key, key);
+    }
+    let mut keys: Vec<usize> = map.iter().map(|(key, _)| *key).collect();
+    keys.sort();
+    expected.sort();
+    assert_eq!(keys, expected);
+    map.values_mut().for_each(|value| *value += 1);
+    assert!(expected.iter().all(|key| map.get(key) == Some(&(key + 1))));
+    let mut drained: Vec<usize> = map.into_iter().map(|(key, _)| key).collect();
+    drained.sort();
+    assert_eq!(drained, expected);
+  }
+
+  #[test]
+  fn var_map_typed_access() {
+    let mut vars = VarMap::new();
+    vars.insert("port", 8080u16);
+    vars.insert("host", String::from("localhost"));
+    assert_eq!(vars.get::<u16>("port"), Some(&8080));
+    assert_eq!(vars.get::<u32>("port"), None);
+    *vars.get_mut::<String>("host").unwrap() += ":1";
+    assert_eq!(vars.get::<String>("host").map(String::as_str), Some("localhost:1"));
+    vars.insert("port", "http");
+    assert_eq!(vars.type_name("port"), Some("&str"));
+    assert_eq!(vars.len(), 2);
+  }
+
+  #[test]
+  fn nested_map_paths() {
+    let mut config = NestedMap::<String>::new();
+    config.insert_path("server.tls.cert", "cert.pem".to_string());
+    config.insert_path("server.port", "443".to_string());
+    config.insert_path("name", "demo".to_string());
+    assert_eq!(config.get_path("server.tls.cert").map(String::as_str), Some("cert.pem"));
+    assert_eq!(config.get_path("server.port").map(String::as_str), Some("443"));
+    assert_eq!(config.get_path("name").map(String::as_str), Some("demo"));
+    assert_eq!(config.get_path("server.tls"), None);
+    assert_eq!(config.get_path("client.port"), None);
+    assert_eq!(config.level("server.tls").map(HashMap::len), Some(1));
+    config.get_path_mut("server.port").unwrap().push('3');
+    assert_eq!(config.get_path("server.port").map(String::as_str), Some("4433"));
+  }
+
+  #[test]
+  fn overlay_resolves_highest_layer() {
+    let mut defaults = HashMap::<&str, u32>::new();
+    defaults.insert("port", 80);
+    defaults.insert("workers", 4);
+    let mut cli = HashMap::<&str, u32>::new();
+    cli.insert("port", 8080);
+    let overlay = OverlayMap::new().with_layer(defaults).with_layer(cli);
+    assert_eq!(overlay.get(&"port"), Some(&8080));
+    assert_eq!(overlay.get(&"workers"), Some(&4));
+    assert_eq!(overlay.source(&"workers"), Some(0));
+    assert_eq!(overlay.get(&"missing"), None);
+    let flat = overlay.flatten();
+    assert_eq!(flat.len(), 2);
+    assert_eq!(flat.get(&"port"), Some(&8080));
+  }
+
+  #[test]
+  fn hash_join_matches_both_orders() {
+    let users = [(1u32, "ann"), (2, "bob"), (3, "cid")];
+    let orders = [(10u32, 1u32), (11, 2), (12, 1), (13, 4)];
+    let mut joined: Vec<(&str, u32)> = hash_join(users.iter(), orders.iter(), |user| user.0, |order| order.1)
+      .map(|(user, order)| (user.1, order.0))
+      .collect();
+    joined.sort();
+    assert_eq!(joined, vec![("ann", 10), ("ann", 12), ("bob", 11)]);
+    let reversed = hash_join(orders.iter(), users.iter().take(1), |order| order.1, |user| user.0).count();
+    assert_eq!(reversed, 2);
+  }
+
+  #[test]
+  fn repl_drives_the_map() {
+    let input = "insert a 1\ninsert b two words\nget b\nget c\nstats\nbogus\nquit\nget a\n";
+    let mut output = Vec::new();
+    repl::run(input.as_bytes(), &mut output, false).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[..4], ["ok", "ok", "two words", "(none)"]);
+    assert!(lines[4].starts_with("len=2 buckets=8 load=0.25 max_displacement="));
+    assert_eq!(lines[5..], ["unknown command `bogus`, type `help` for commands"]);
+  }
+
+  #[test]
+  fn crc32c_matches_reference() {
+    assert_eq!(hash::crc32c(b"123456789"), 0xE3069283);
+    assert_eq!(hash::crc32c_scalar(b"123456789"), 0xE3069283);
+    let long = b"the quick brown fox jumps over the lazy dog";
+    assert_eq!(hash::crc32c(long), hash::crc32c_scalar(long));
+    let mut map = HashMap::<hash::Crc32<u64>, u64>::new();
+    map.insert(hash::Crc32(7), 49);
+    assert_eq!(map.get(&hash::Crc32(7)), Some(&49));
+  }
+
+  #[test]
+  fn byte_hashes() {
+    assert_eq!(hash::xxh64(b"", 0), 0xEF46DB3751D8E999);
+    assert_eq!(hash::xxh64(b"abc", 0), 0x44BC2CF5AD770999);
+    assert_eq!(hash::wyhash(b"", 0), 0x93228A4DE0EEC5A2);
+    let inputs: Vec<Vec<u8>> = (0..100u8).map(|length| (0..length).collect()).collect();
+    let mut seen = std::collections::HashSet::new();
+    for input in inputs.iter() {
+      assert!(seen.insert(hash::wyhash(input, 0)));
+      assert!(seen.insert(hash::xxh64(input, 0)));
+    }
+    assert_ne!(hash::wyhash(b"key", 0), hash::wyhash(b"key", 1));
+    assert_eq!("key".hash(), String::from("key").hash());
+  }
+
+  #[test]
+  fn iter_prefix_filters_keys() {
+    let mut map = HashMap::<String, u32>::new();
+    map.insert("user.name".to_string(), 1);
+    map.insert("user.id".to_string(), 2);
+    map.insert("session".to_string(), 3);
+    let mut keys: Vec<&String> = map.iter_prefix("user.").map(|(key, _)| key).collect();
+    keys.sort();
+    assert_eq!(keys, ["user.id", "user.name"]);
+    let mut borrowed = HashMap::<&str, u32>::new();
+    borrowed.insert("user.name", 1);
+    assert_eq!(borrowed.iter_prefix("sess").count(), 0);
+  }
+
+  #[test]
+  fn argmin_argmax() {
+    let mut hits = HashMap::<&str, u32>::new();
+    assert_eq!(hits.max_by_value(), None);
+    hits.insert("home", 40);
+    hits.insert("about", 3);
+    hits.insert("blog", 17);
+    assert_eq!(hits.max_by_value(), Some((&"home", &40)));
+    assert_eq!(hits.min_by_value(), Some((&"about", &3)));
+    assert_eq!(hits.max_by_key_fn(|key, _| key.len()), Some((&"about", &3)));
+    assert_eq!(hits.min_by_key_fn(|_, value| value % 10), Some((&"home", &40)));
+  }
+
+  #[test]
+  fn aggregation() {
+    let mut counts = HashMap::<&str, u32>::new();
+    for word in "a b a c a b".split(' ') {
+      counts.insert_or_reduce(word, 1, |count, one| *count += one);
+    }
+    assert_eq!((counts.get(&"a"), counts.get(&"b"), counts.get(&"c")), (Some(&3), Some(&2), Some(&1)));
+    let lengths = HashMap::aggregate(["apple", "avocado", "banana"], |word| word.chars().next().unwrap(), || 0usize, |total, word| *total += word.len());
+    assert_eq!(lengths.get(&'a'), Some(&12));
+    assert_eq!(lengths.get(&'b'), Some(&6));
+  }
+
+  #[test]
+  fn nested_hash_map_two_levels() {
+    let mut grid = NestedHashMap::<u32, u32, char>::new();
+    grid.insert(0, 0, 'a');
+    grid.insert(0, 1, 'b');
+    grid.insert(2, 5, 'c');
+    grid.insert(0, 1, 'B');
+    assert_eq!(grid.len(), 3);
+    assert_eq!(grid.get(&0, &1), Some(&'B'));
+    assert_eq!(grid.get(&1, &0), None);
+    *grid.get_mut(&2, &5).unwrap() = 'C';
+    assert_eq!(grid.inner_map(&2).and_then(|row| row.get(&5)), Some(&'C'));
+    assert_eq!(grid.iter().count(), 3);
+    assert_eq!(grid.remove_inner(&0, &0), Some('a'));
+    assert_eq!(grid.remove_inner(&0, &1), Some('B'));
+    assert!(grid.inner_map(&0).is_none());
+    assert_eq!(grid.get(&2, &5), Some(&'C'));
+    assert_eq!(grid.len(), 1);
+  }
+
+  #[test]
+  fn remove_keeps_probe_chains_intact() {
+    let mut map = HashMap::<u32, u32>::with_capacity(16);
+    // 4, 7 and 48 share home bucket 4; 1 is displaced by them.
+    for key in [4, 7, 1, 48] {
+      map.insert(key, key * 10);
+    }
+    assert_eq!(map.remove(&7), Some(70));
+    assert_eq!(map.remove(&7), None);
+    assert_eq!(map.get(&48), Some(&480));
+    assert_eq!(map.get(&1), Some(&10));
+    assert_eq!(map.remove(&4), Some(40));
+    assert_eq!(map.get(&48), Some(&480));
+    assert_eq!(map.get(&1), Some(&10));
+    assert_eq!(map.len(), 2);
+    assert!(map.dump().slots.iter().all(|slot| slot.displacement.unwrap_or(0) <= 1));
+    map.insert(7, 1);
+    assert_eq!(map.iter().count(), 3);
+  }
+
+  #[test]
+  fn contains_key_tracks_inserts_and_removes() {
+    let mut map = HashMap::<&str, ()>::new();
+    assert!(!map.contains_key(&"a"));
+    map.insert("a", ());
+    assert!(map.contains_key(&"a"));
+    map.remove(&"a");
+    assert!(!map.contains_key(&"a"));
+  }
+
+  #[test]
+  fn insert_returns_previous_value() {
+    let mut map = HashMap::<u32, &str>::new();
+    assert_eq!(map.insert(1, "a"), None);
+    assert_eq!(map.insert(1, "b"), Some("a"));
+    assert_eq!(map.get(&1), Some(&"b"));
+    assert_eq!(map.len(), 1);
+  }
+
+  #[test]
+  fn entry_api() {
+    let mut counts = HashMap::<&str, u32>::new();
+    for word in ["a", "b", "a"] {
+      *counts.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(counts.get(&"a"), Some(&2));
+    counts.entry("b").and_modify(|count| *count *= 10).or_default();
+    counts.entry("c").and_modify(|count| *count *= 10).or_default();
+    assert_eq!((counts.get(&"b"), counts.get(&"c")), (Some(&10), Some(&0)));
+    assert_eq!(*counts.entry("dddd").or_insert_with_key(|key| key.len() as u32), 4);
+    match counts.entry("a") {
+      Entry::Occupied(entry) => assert_eq!(entry.remove(), 2),
+      Entry::Vacant(_) => unreachable!(),
+    }
+    assert!(!counts.contains_key(&"a"));
+    assert_eq!(counts.len(), 3);
+  }
+
+  #[test]
+  fn replace_entry_with_keeps_or_removes() {
+    let mut map: HashMap<u32, u32> = (0..100).map(|n| (n, n)).collect();
+    let Entry::Occupied(entry) = map.entry(7) else { unreachable!() };
+    match entry.replace_entry_with(|key, value| Some(key + value * 10)) {
+      Entry::Occupied(entry) => assert_eq!((*entry.key(), *entry.get()), (7, 77)),
+      Entry::Vacant(_) => panic!("kept entry reported vacant"),
+    }
+    let Entry::Occupied(entry) = map.entry(8) else { unreachable!() };
+    match entry.replace_entry_with(|_, _| None) {
+      Entry::Occupied(_) => panic!("removed entry reported occupied"),
+      Entry::Vacant(entry) => assert_eq!(*entry.insert(5), 5),
+    }
+    map.remove(&8);
+    assert_eq!(map.len(), 99);
+    assert_eq!(map.get(&7), Some(&77));
+    assert!(!map.contains_key(&8));
+    assert!((0..100).filter(|&n| n != 7 && n != 8).all(|n| map.get(&n) == Some(&n)));
+  }
+
+  #[test]
+  fn get_key_value_returns_stored_key() {
+    let mut map = HashMap::<hash::Djb2<String>, u32>::new();
+    map.insert(hash::Djb2("id".to_string()), 7);
+    let (key, value) = map.get_key_value(&hash::Djb2("id".to_string())).unwrap();
+    assert_eq!((key.0.as_str(), *value), ("id", 7));
+    assert_eq!(map.get_key_value(&hash::Djb2("other".to_string())), None);
+  }
+
+  #[test]
+  fn get_many_mut_requires_distinct_keys() {
+    let mut map = HashMap::<char, u32>::new();
+    map.insert('a', 1);
+    map.insert('b', 2);
+    if let Some([a, b]) = map.get_many_mut([&'a', &'b']) {
+      std::mem::swap(a, b);
+    }
+    assert_eq!((map.get(&'a'), map.get(&'b')), (Some(&2), Some(&1)));
+    assert!(map.get_many_mut([&'a', &'a']).is_none());
+    assert!(map.get_many_mut([&'a', &'z']).is_none());
+  }
+
+  #[test]
+  fn existing_keys_never_trigger_a_resize() {
+    let mut map = HashMap::<u32, u32>::with_capacity(4);
+    map.insert(1, 1);
+    map.insert(2, 2);
+    assert!(map.try_insert(1, 10).is_err());
+    *map.entry(2).or_insert(0) += 1;
+    map.insert_or_reduce(1, 5, |total, value| *total += value);
+    map.raw_entry_mut().from_key(&2).or_insert(2, 0);
+    assert_eq!(map.bucket_count(), 4);
+    assert_eq!((map.get(&1), map.get(&2)), (Some(&6), Some(&3)));
+    map.entry(3).or_insert(3);
+    assert!(map.bucket_count() > 4);
+  }
+
+  #[test]
+  fn try_insert_rejects_existing_keys() {
+    let mut registry = HashMap::<&str, u32>::new();
+    assert_eq!(registry.try_insert("http", 80).ok().copied(), Some(80));
+    let error = registry.try_insert("http", 8080).unwrap_err();
+    assert_eq!((*error.entry.key(), *error.entry.get(), error.value), ("http", 80, 8080));
+    assert_eq!(error.to_string(), "failed to insert 8080, key \"http\" already exists with value 80");
+    assert_eq!(registry.get(&"http"), Some(&80));
+  }
+
+  #[test]
+  fn clear_keeps_capacity() {
+    let mut map = HashMap::<u32, u32>::new();
+    for i in 0..100 {
+      map.insert(i, i);
+    }
+    let capacity = map.capacity();
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.iter().count(), 0);
+    assert_eq!(map.get(&5), None);
+    assert_eq!(map.capacity(), capacity);
+    map.insert(5, 50);
+    assert_eq!(map.get(&5), Some(&50));
+  }
+
+  #[test]
+  fn extract_if_removes_matching_entries() {
+    let mut map = HashMap::<usize, usize>::with_capacity(16);
+    // Three keys homed at slot 1, two at 2 and two at 15, so clusters merge and wrap around the
+    // end, and extraction runs through backward shifts.
+    let home_1: Vec<usize> = keys_homed_at(1, 16).take(3).collect();
+    let home_2: Vec<usize> = keys_homed_at(2, 16).take(2).collect();
+    let home_15: Vec<usize> = keys_homed_at(15, 16).take(2).collect();
+    for &key in home_1.iter().chain(&home_2).chain(&home_15) {
+      map.insert(key, key);
+    }
+    let mut extracted: Vec<usize> = map.extract_if(|key, _| !home_2.contains(key)).map(|(key, _)| key).collect();
+    extracted.sort();
+    let mut expected = [home_1, home_15].concat();
+    expected.sort();
+    assert_eq!(extracted, expected);
+    assert_eq!(map.len(), 2);
+    assert!(home_2.iter().all(|key| map.get(key) == Some(key)));
+  }
+
+  #[test]
+  fn keys_values_and_values_mut() {
+    let mut map = HashMap::<u32, u32>::new();
+    for i in 1..=4 {
+      map.insert(i, i * 10);
+    }
+    let mut keys: Vec<u32> = map.keys().copied().collect();
+    keys.sort();
+    assert_eq!(keys, vec![1, 2, 3, 4]);
+    assert_eq!(map.values().sum::<u32>(), 100);
+    for value in map.values_mut() {
+      *value += 1;
+    }
+    assert_eq!(map.values().sum::<u32>(), 104);
+  }
+
+  #[test]
+  fn iter_mut_updates_values_in_place() {
+    let mut map = HashMap::<u32, u32>::new();
+    for i in 0..10 {
+      map.insert(i, 0);
+    }
+    for (key, value) in map.iter_mut() {
+      *value = key * key;
+    }
+    assert_eq!(map.get(&7), Some(&49));
+    assert_eq!(map.iter_mut().count(), 10);
+  }
+
+  #[test]
+  fn into_iterator_for_owned_and_borrowed_maps() {
+    let mut map = HashMap::<u32, String>::new();
+    map.insert(1, "one".to_string());
+    map.insert(2, "two".to_string());
+    for (_, value) in &mut map {
+      value.push('!');
+    }
+    let mut borrowed: Vec<(&u32, &String)> = (&map).into_iter().collect();
+    borrowed.sort();
+    assert_eq!(borrowed[0], (&1, &"one!".to_string()));
+    let mut owned: Vec<(u32, String)> = map.into_iter().collect();
+    owned.sort();
+    assert_eq!(owned, vec![(1, "one!".to_string()), (2, "two!".to_string())]);
+  }
+
+  #[test]
+  fn collect_and_extend() {
+    let map: HashMap<u32, u32> = (0..1000).map(|i| (i, i * 2)).collect();
+    assert_eq!(map.len(), 1000);
+    assert_eq!(map.get(&999), Some(&1998));
+    assert!(map.capacity() >= 1000);
+    let mut copy = HashMap::<u32, u32>::new();
+    copy.extend(map.iter());
+    copy.extend([(5000, 1)]);
+    assert_eq!(copy.len(), 1001);
+    let empty: HashMap<u32, u32> = std::iter::empty().collect();
+    assert!(empty.is_empty());
+  }
+
+  #[test]
+  fn from_array() {
+    let map = HashMap::from([(1, "a"), (2, "b"), (1, "c")]);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&1), Some(&"c"));
+  }
+
+  #[test]
+  fn index_operators() {
+    let mut map = HashMap::from([("a", 1), ("b", 2)]);
+    map[&"a"] += 10;
+    assert_eq!(map[&"a"], 11);
+  }
+
+  #[test]
+  #[should_panic(expected = "not found")]
+  fn index_panics_on_missing_key() {
+    let map = HashMap::from([("a", 1)]);
+    let _ = map[&"z"];
+  }
+
+  #[test]
+  fn index_works_without_debug_keys() {
+    #[derive(PartialEq)]
+    struct Opaque(u32);
+    impl Hashable for Opaque {
+      fn hash(&self) -> usize {
+        self.0 as usize
+      }
+    }
+    let mut map = HashMap::new();
+    map.insert(Opaque(1), 10);
+    map[&Opaque(1)] += 1;
+    assert_eq!(map[&Opaque(1)], 11);
+  }
+
+  #[test]
+  fn equality_ignores_layout() {
+    let mut small = HashMap::<u32, u32>::with_capacity(4);
+    let mut large = HashMap::<u32, u32>::with_capacity(256);
+    for i in 0..10 {
+      small.insert(i, i);
+      large.insert(9 - i, 9 - i);
+    }
+    assert_eq!(small, large);
+    large.insert(3, 0);
+    assert_ne!(small, large);
+    large.insert(3, 3);
+    large.insert(10, 10);
+    assert_ne!(small, large);
+  }
+
+  #[test]
+  fn clone_is_independent() {
+    let mut map = HashMap::from([(1, "a".to_string())]);
+    let snapshot = map.clone();
+    map.get_mut(&1).unwrap().push('!');
+    map.insert(2, "b".to_string());
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot.get(&1).map(String::as_str), Some("a"));
+  }
+
+  #[test]
+  fn debug_prints_entries_only() {
+    let mut map = HashMap::<u32, &str>::with_capacity(16);
+    assert_eq!(format!("{:?}", map), "{}");
+    map.insert(3, "c");
+    assert_eq!(format!("{:?}", map), "{3: \"c\"}");
+  }
+
+  #[test]
+  fn shrink_after_removals() {
+    let mut map: HashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    for i in 10..1000 {
+      map.remove(&i);
+    }
+    map.shrink_to(100);
+    assert_eq!((map.capacity(), map.bucket_count()), (100, 200));
+    map.shrink_to_fit();
+    assert_eq!((map.capacity(), map.bucket_count()), (10, 20));
+    assert_eq!(map.len(), 10);
+    assert!((0..10).all(|i| map.get(&i) == Some(&i)));
+    map.shrink_to(1000);
+    assert_eq!(map.capacity(), 10);
+  }
+
+  #[cfg(feature = "profiling")]
+  #[test]
+  fn reserve_avoids_incremental_resizes() {
+    let mut map = HashMap::<u32, u32>::new();
+    map.reserve(10_000);
+    let resizes = map.metrics().resizes;
+    for i in 0..10_000 {
+      map.insert(i, i);
+    }
+    assert_eq!(map.metrics().resizes, resizes);
+  }
+
+  #[test]
+  fn try_reserve_reports_failures() {
+    let mut map = HashMap::<u64, u64>::new();
+    assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    assert_eq!(map.try_reserve(usize::MAX / 4), Err(TryReserveError::CapacityOverflow));
+    assert!(map.try_reserve(1000).is_ok());
+    assert!(map.capacity() >= 1000);
+    assert_eq!(HashMap::<u64, u64>::try_with_capacity(usize::MAX / 2).err(), Some(TryReserveError::CapacityOverflow));
+    assert!(matches!(HashMap::<u8, u8>::try_with_capacity(isize::MAX as usize / 16), Err(TryReserveError::AllocError(_))));
+  }
+
+  #[test]
+  fn split_off_moves_entries() {
+    let mut map = HashMap::<u32, u32>::with_load_factor(0.9);
+    map.extend((0..20).map(|i| (i, i)));
+    let even = map.split_off(|key, _| key % 2 == 0);
+    assert_eq!((map.len(), even.len()), (10, 10));
+    assert_eq!(even.max_load_factor(), 0.9);
+    assert!(even.bucket_count() < 64);
+    assert!(even.keys().all(|key| key % 2 == 0));
+    let picked = map.split_off_keys([&1, &3, &4]);
+    assert_eq!(picked, HashMap::from([(1, 1), (3, 3)]));
+    assert_eq!(map.len(), 8);
+  }
+
+  #[test]
+  fn append_moves_and_replaces() {
+    let mut map = HashMap::from([(1, "a"), (2, "b")]);
+    let mut other = HashMap::from([(2, "B"), (3, "C")]);
+    let slots = other.entries.keys.as_ptr();
+    map.append(&mut other);
+    assert_eq!(map, HashMap::from([(1, "a"), (2, "B"), (3, "C")]));
+    assert!(other.is_empty());
+    assert_eq!(other.entries.keys.as_ptr(), slots);
+    other.insert(4, "d");
+    assert_eq!(other.get(&4), Some(&"d"));
+  }
+
+  #[test]
+  fn get_or_insert_with_memoizes() {
+    let mut cache = HashMap::<u64, u64>::new();
+    let mut calls = 0;
+    for _ in 0..3 {
+      let value = *cache.get_or_insert_with(10, || {
+        calls += 1;
+        (1..=10).product()
+      });
+      assert_eq!(value, 3_628_800);
+    }
+    assert_eq!(calls, 1);
+  }
+
+  #[test]
+  fn update_applies_only_to_present_keys() {
+    let mut map = HashMap::from([("hits", 1)]);
+    assert!(map.update(&"hits", |hits| *hits += 1));
+    assert!(!map.update(&"misses", |misses| *misses += 1));
+    assert_eq!(map, HashMap::from([("hits", 2)]));
+  }
+
+  #[test]
+  fn pop_drains_every_entry() {
+    let mut map: HashMap<i32, i32> = (0..100).map(|n| (n, n * n)).collect();
+    let mut popped = Vec::new();
+    while let Some((key, value)) = map.pop() {
+      assert_eq!(value, key * key);
+      popped.push(key);
+    }
+    popped.sort();
+    assert_eq!(popped, (0..100).collect::<Vec<_>>());
+    assert!(map.is_empty());
+  }
+
+  #[test]
+  fn pop_sees_entries_inserted_while_draining() {
+    let mut map: HashMap<u32, u32> = (0..1000).map(|n| (n, n)).collect();
+    let mut popped = 0;
+    while let Some((key, _)) = map.pop() {
+      popped += 1;
+      if key < 1000 && key % 10 == 0 {
+        map.insert(key + 1000, key);
+      }
+    }
+    assert_eq!(popped, 1100);
+  }
+
+  #[test]
+  fn remove_entry_returns_the_stored_key() {
+    #[derive(Clone, Default, Debug)]
+    struct Interned(&'static str, u32);
+    impl PartialEq for Interned {
+      fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+      }
+    }
+    impl Hashable for Interned {
+      fn hash(&self) -> usize {
+        self.0.hash()
+      }
+    }
+
+    let mut map = HashMap::new();
+    map.insert(Interned("alpha", 7), 1);
+    let (key, value) = map.remove_entry(&Interned("alpha", 0)).unwrap();
+    assert_eq!((key.1, value), (7, 1));
+    assert_eq!(map.remove_entry(&Interned("alpha", 0)).map(|(_, value)| value), None);
+  }
+
+  #[test]
+  fn lookups_accept_borrowed_keys() {
+    let mut names = HashMap::<String, u32>::new();
+    names.insert("alice".to_string(), 1);
+    assert_eq!(names.get("alice"), Some(&1));
+    assert!(names.contains_key("alice"));
+    assert_eq!(names.remove("alice"), Some(1));
+    assert!(!names.contains_key("alice"));
+
+    let mut blobs = HashMap::<Vec<u8>, u32>::new();
+    blobs.insert(b"raw".to_vec(), 2);
+    assert_eq!(blobs.get(&b"raw"[..]), Some(&2));
+    assert_eq!(blobs.remove(&b"raw"[..]), Some(2));
+  }
+
+  #[test]
+  fn entry_ref_converts_the_key_only_when_vacant() {
+    let mut counts = HashMap::<String, u32>::new();
+    for word in "to be or not to be".split(' ') {
+      *counts.entry_ref(word).or_default() += 1;
+    }
+    assert_eq!(counts.get("to"), Some(&2));
+    assert_eq!(counts.get("not"), Some(&1));
+    assert_eq!(counts.entry_ref("be").key(), "be");
+    assert_eq!(counts.len(), 4);
+  }
+
+  #[test]
+  fn raw_entry_reuses_precomputed_hashes() {
+    let mut map = HashMap::<String, u32>::new();
+    let hash = "pipeline".hash();
+    *map.raw_entry_mut().from_hash(hash, |key| key == "pipeline").or_insert("pipeline".to_string(), 0) += 1;
+    *map.raw_entry_mut().from_hash(hash, |key| key == "pipeline").or_insert("pipeline".to_string(), 0) += 1;
+    assert_eq!(map.raw_entry().from_hash(hash, |key| key == "pipeline"), Some((&"pipeline".to_string(), &2)));
+    assert_eq!(map.raw_entry().from_key(&"other".to_string()), None);
+    assert_eq!(map.get("pipeline"), Some(&2));
+  }
+
+  #[test]
+  fn iterators_report_exact_lengths() {
+    let mut map: HashMap<u32, u32> = (0..10).map(|n| (n, n)).collect();
+    let mut iter = map.iter();
+    assert_eq!(iter.len(), 10);
+    iter.next();
+    assert_eq!(iter.size_hint(), (9, Some(9)));
+    assert_eq!(iter.by_ref().count(), 9);
+    assert_eq!(iter.next(), None);
+    assert_eq!((map.keys().len(), map.values().len()), (10, 10));
+    assert_eq!((map.iter_mut().len(), map.values_mut().len()), (10, 10));
+    assert_eq!(map.extract_if(|_, _| false).size_hint(), (0, Some(10)));
+    assert_eq!(map.into_iter().len(), 10);
+  }
+
+  #[test]
+  fn cursor_mut_edits_while_traversing() {
+    let mut map: HashMap<u32, u32> = (0..200).map(|n| (n, n)).collect();
+    let mut cursor = map.cursor_mut();
+    let mut visited = 0;
+    while let Some((&key, _)) = cursor.next() {
+      visited += 1;
+      if key % 2 == 0 {
+        assert_eq!(cursor.remove_current(), Some((key, key)));
+        assert!(cursor.current().is_none());
+      } else {
+        assert_eq!(cursor.replace_current(key * 10), Some(key));
+      }
+    }
+    assert_eq!(visited, 200);
+    assert_eq!(map.len(), 100);
+    assert!(map.iter().all(|(key, value)| key % 2 == 1 && *value == key * 10));
+  }
+
+  #[test]
+  fn stores_keys_and_values_without_clone_or_default() {
+    #[derive(Debug, PartialEq)]
+    struct Id(u32);
+    impl Hashable for Id {
+      fn hash(&self) -> usize {
+        self.0 as usize
+      }
+    }
+    #[derive(Debug)]
+    struct Handle(Box<u32>);
+
+    let mut map = HashMap::new();
+    for id in 0..100 {
+      map.insert(Id(id), Handle(Box::new(id)));
+    }
+    assert_eq!(map.get(&Id(42)).map(|handle| *handle.0), Some(42));
+    assert_eq!(map.remove(&Id(7)).map(|handle| *handle.0), Some(7));
+    assert_eq!(map.len(), 99);
+  }
+
+  #[test]
+  fn stores_keys_and_values_without_debug() {
+    #[derive(PartialEq)]
+    struct Opaque(u8);
+    impl Hashable for Opaque {
+      fn hash(&self) -> usize {
+        self.0 as usize
+      }
+    }
+
+    let mut map = HashMap::new();
+    map.insert(Opaque(1), Opaque(2));
+    *map.entry(Opaque(3)).or_insert(Opaque(0)) = Opaque(4);
+    assert!(map.get(&Opaque(1)) == Some(&Opaque(2)));
+    assert!(map.get(&Opaque(3)) == Some(&Opaque(4)));
+  }
+
+  #[cfg(feature = "profiling")]
+  #[test]
+  fn resizing_moves_entries_without_comparing_keys() {
+    #[derive(PartialEq)]
+    struct Colliding(u32);
+    impl Hashable for Colliding {
+      fn hash(&self) -> usize {
+        0
+      }
+    }
+
+    let mut map = HashMap::with_capacity(8);
+    for n in 0..3 {
+      map.insert(Colliding(n), n);
+    }
+    map.reset_metrics();
+    map.reserve(100);
+    assert_eq!((map.metrics().comparisons, map.metrics().resizes), (0, 1));
+    assert_eq!(map.get(&Colliding(2)), Some(&2));
+  }
+
+  #[test]
+  fn resizing_reuses_cached_hashes() {
+    use std::cell::Cell;
+    thread_local! {
+      static HASHES: Cell<usize> = const { Cell::new(0) };
+    }
+    #[derive(PartialEq)]
+    struct Counted(u32);
+    impl Hashable for Counted {
+      fn hash(&self) -> usize {
+        HASHES.with(|hashes| hashes.set(hashes.get() + 1));
+        self.0 as usize
+      }
+    }
+
+    let mut map = HashMap::with_capacity(4);
+    for n in 0..100 {
+      map.insert(Counted(n), n);
+    }
+    assert_eq!(HASHES.with(Cell::get), 100);
+  }
+
+  #[test]
+  fn zero_capacity_maps_allocate_on_first_insert() {
+    for mut map in [HashMap::<String, u32>::empty(), HashMap::with_capacity(0)] {
+      assert_eq!(map.capacity(), 0);
+      assert_eq!(map.get("missing"), None);
+      assert_eq!(map.remove("missing"), None);
+      assert!(map.iter().next().is_none());
+      map.insert("first".to_string(), 1);
+      *map.entry("second".to_string()).or_default() += 2;
+      assert_eq!((map.get("first"), map.get("second")), (Some(&1), Some(&2)));
+    }
+    let mut map = HashMap::from([(1, 1)]);
+    map.remove(&1);
+    map.shrink_to_fit();
+    assert_eq!((map.capacity(), map.get(&1)), (0, None));
+  }
+
+  #[test]
+  fn with_capacity_for_fits_without_resizing() {
+    for entries in [0, 1, 7, 100, 1000] {
+      let mut map = HashMap::<u32, u32>::with_capacity_for(entries);
+      assert!(map.capacity() >= entries);
+      let buckets = map.bucket_count();
+      map.extend((0..entries as u32).map(|n| (n, n)));
+      assert_eq!(map.bucket_count(), buckets);
+    }
+    assert_eq!(HashMap::<u32, u32>::with_capacity(14).capacity(), 7);
+  }
+
+  #[test]
+  fn load_factor_controls_growth() {
+    let mut dense = HashMap::<u32, u32>::with_load_factor(0.9);
+    dense.extend((0..57).map(|n| (n, n)));
+    assert_eq!((dense.capacity(), dense.bucket_count()), (57, 64));
+    dense.insert(57, 57);
+    assert_eq!(dense.bucket_count(), 129);
+    assert_eq!(dense.max_load_factor(), 0.9);
+
+    let mut exact = HashMap::<u32, u32>::with_capacity(20);
+    exact.set_max_load_factor(0.9);
+    exact.extend((0..18).map(|n| (n, n)));
+    assert_eq!(exact.bucket_count(), 20);
+
+    dense.reserve(200);
+    assert!(dense.capacity() >= 258);
+    dense.set_max_load_factor(0.25);
+    assert!(dense.capacity() >= 58);
+    assert!((0..58).all(|n| dense.get(&n) == Some(&n)));
+  }
+
+  #[test]
+  #[should_panic(expected = "max load factor")]
+  fn load_factor_must_leave_an_empty_slot() {
+    HashMap::<u32, u32>::with_load_factor(1.0);
+  }
+
+  #[test]
+  fn robin_hood_keeps_clusters_sorted_by_home() {
+    let mut map = HashMap::with_load_factor(0.9);
+    for n in 0..2000 {
+      map.insert(n.to_string(), n);
+    }
+    for n in (0..2000).step_by(3) {
+      map.remove(&n.to_string());
+    }
+    for pair in map.dump().slots.windows(2) {
+      if let (Some(previous), Some(next)) = (pair[0].displacement, pair[1].displacement) {
+        assert!(next <= previous + 1);
+      }
+    }
+    assert!((0..2000).all(|n| map.get(&n.to_string()) == if n % 3 == 0 { None } else { Some(&n) }));
+  }
+
+  #[test]
+  fn group_probing_wraps_around_the_table() {
+    let mut map = HashMap::<u32, u32>::with_capacity(20);
+    map.set_max_load_factor(0.9);
+    // Every key hashes to the last slot, so the cluster wraps around to the start.
+    let mut keys: Vec<u32> = keys_homed_at(19, 20).map(|key| key as u32).take(19).collect();
+    let absent = keys.pop().unwrap();
+    for &key in &keys {
+      map.insert(key, key);
+    }
+    assert_eq!(map.bucket_count(), 20);
+    assert!(keys.iter().all(|key| map.get(key) == Some(key)));
+    assert_eq!(map.get(&absent), None);
+    for key in keys.iter().step_by(2) {
+      map.remove(key);
+    }
+    assert!(keys.iter().enumerate().all(|(n, key)| map.contains_key(key) == (n % 2 == 1)));
+  }
+
+  #[test]
+  fn hopscotch_keeps_entries_near_home() {
+    let mut map = HopscotchMap::with_capacity(64);
+    for n in 0..2000u32 {
+      assert_eq!(map.insert(n * 64, n), None);
+    }
+    assert_eq!(map.insert(0, 7), Some(0));
+    assert_eq!(map.len(), 2000);
+    assert!((1..2000u32).all(|n| map.get(&(n * 64)) == Some(&n)));
+    for n in (0..2000u32).step_by(2) {
+      assert!(map.remove(&(n * 64)).is_some());
+    }
+    assert_eq!(map.len(), 1000);
+    assert!((0..2000u32).all(|n| map.contains_key(&(n * 64)) == (n % 2 == 1)));
+    assert_eq!(map.iter().count(), 1000);
+  }
+
+  #[test]
+  #[should_panic(expected = "share a hash")]
+  fn hopscotch_panics_when_a_neighborhood_overflows() {
+    #[derive(PartialEq)]
+    struct Colliding(u32);
+    impl Hashable for Colliding {
+      fn hash(&self) -> usize {
+        0
+      }
+    }
+
+    let mut map = HopscotchMap::with_capacity(64);
+    for n in 0..32 {
+      map.insert(Colliding(n), n);
+    }
+    assert_eq!(map.len(), 32);
+    map.insert(Colliding(32), 32);
+  }
+
+  #[test]
+  fn cuckoo_map_finds_keys_in_one_of_two_slots() {
+    let mut map = CuckooMap::with_capacity(8);
+    for n in 0..1000u32 {
+      assert_eq!(map.insert(n.to_string(), n), None);
+    }
+    assert_eq!(map.insert("0".to_string(), 7), Some(0));
+    assert_eq!(map.len(), 1000);
+    assert!((1..1000u32).all(|n| map.get(&n.to_string()) == Some(&n)));
+    for n in (0..1000u32).step_by(2) {
+      assert!(map.remove(&n.to_string()).is_some());
+    }
+    assert!((0..1000u32).all(|n| map.contains_key(&n.to_string()) == (n % 2 == 1)));
+    assert_eq!(map.iter().count(), 500);
+  }
+
+  #[test]
+  #[should_panic(expected = "share a hash")]
+  fn cuckoo_map_panics_on_three_colliding_keys() {
+    #[derive(PartialEq)]
+    struct Colliding(u32);
+    impl Hashable for Colliding {
+      fn hash(&self) -> usize {
+        0
+      }
+    }
+
+    let mut map = CuckooMap::with_capacity(8);
+    map.insert(Colliding(0), 0);
+    map.insert(Colliding(1), 1);
+    assert_eq!(map.len(), 2);
+    map.insert(Colliding(2), 2);
+  }
+
+  #[test]
+  fn strided_integer_keys_spread_over_buckets() {
+    // Under the identity hash every key would share home slot 0 of this 1024-slot table.
+    let mut map = HashMap::<u64, u64>::with_capacity(1024);
+    for n in 0..256 {
+      map.insert(n * 1024, n);
+    }
+    let max_displacement = map.dump().slots.iter().filter_map(|slot| slot.displacement).max();
+    assert!(max_displacement < Some(16), "max displacement {:?}", max_displacement);
+  }
+
+  #[test]
+  fn unchecked_insert_and_access() {
+    let mut map = HashMap::<u32, u32>::with_capacity(4);
+    for n in 0..100 {
+      // SAFETY: every key is inserted once.
+      *unsafe { map.insert_unique_unchecked(n, n) } += 1;
+    }
+    assert_eq!(map.len(), 100);
+    let index = map.get_index(&42).unwrap();
+    // SAFETY: `index` was just returned by `get_index`.
+    unsafe {
+      *map.get_unchecked_mut(index).1 *= 2;
+      assert_eq!(map.get_unchecked(index), (&42, &86));
+    }
+  }
+
+  #[test]
+  fn clone_from_reuses_the_allocation() {
+    let mut state: HashMap<String, u32> = (0..100).map(|n| (n.to_string(), n)).collect();
+    let mut snapshot = state.clone();
+    let hashes = snapshot.entries.hashes.as_ptr();
+    for frame in 0..3 {
+      state.insert(frame.to_string(), frame * 10);
+      state.remove("50");
+      snapshot.clone_from(&state);
+      assert_eq!(snapshot, state);
+      assert_eq!(snapshot.entries.hashes.as_ptr(), hashes);
+    }
+  }
+
+  #[test]
+  fn insert_batch_resizes_once() {
+    let mut map = HashMap::<u32, u32>::new();
+    map.insert(5, 0);
+    map.insert_batch((0..1000).map(|n| (n % 800, n)));
+    let buckets = map.bucket_count();
+    assert_eq!(map.len(), 800);
+    assert_eq!(map.get(&5), Some(&805));
+    assert_eq!(map.get(&799), Some(&799));
+    map.insert_batch(std::iter::empty());
+    assert_eq!(map.bucket_count(), buckets);
+  }
+
+  #[test]
+  fn insert_batch_into_zero_slot_tables() {
+    for mut map in [HashMap::<u32, u32>::empty(), HashMap::with_capacity(0)] {
+      map.insert_batch((0..10).filter(|n| n % 2 == 0).map(|n| (n, n)));
+      assert_eq!(map.len(), 5);
+      assert!((0..10).all(|n| map.get(&n) == (n % 2 == 0).then_some(&n)));
+    }
+  }
+
+  #[test]
+  fn insert_batch_grows_past_an_unknown_length() {
+    let mut map = HashMap::<u32, u32>::new();
+    map.insert_batch((0..1000).filter(|n| n % 3 != 0).map(|n| (n, n * 2)));
+    assert_eq!(map.len(), 666);
+    assert!((0..1000).all(|n| map.get(&n) == (n % 3 != 0).then_some(&(n * 2))));
+  }
+
+  #[test]
+  fn memory_usage_counts_slots_and_contents() {
+    let mut map = HashMap::<String, Vec<u32>>::with_capacity(64);
+    let table = map.memory_usage();
+    assert!(table >= 64 * (size_of::<usize>() + size_of::<Option<String>>() + size_of::<Option<Vec<u32>>>()));
+    assert_eq!(map.deep_memory_usage(), table);
+    map.insert(String::with_capacity(100), Vec::with_capacity(10));
+    assert_eq!(map.memory_usage(), table);
+    assert_eq!(map.deep_memory_usage(), table + 100 + 10 * size_of::<u32>());
+  }
+
+  #[test]
+  fn small_map_spills_past_its_inline_capacity() {
+    let mut map = SmallMap::<u32, u32, 4>::new();
+    for n in 0..4 {
+      assert_eq!(map.insert(n, n), None);
+    }
+    assert_eq!(map.insert(2, 20), Some(2));
+    assert_eq!(map.remove(&0), Some(0));
+    assert_eq!(map.insert(0, 10), None);
+    assert!(map.is_inline());
+    assert_eq!(map.iter().map(|(_, value)| value).sum::<u32>(), 10 + 1 + 20 + 3);
+    map.insert(4, 4);
+    assert!(!map.is_inline());
+    assert_eq!(map.len(), 5);
+    assert!([(0, 10), (1, 1), (2, 20), (3, 3), (4, 4)].iter().all(|(key, value)| map.get(key) == Some(value)));
+    assert_eq!(map.remove(&4), Some(4));
+    assert!(!map.contains_key(&4));
+  }
 
   #[test]
   fn performance() {
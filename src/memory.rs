@@ -0,0 +1,78 @@
+use std::mem::size_of;
+
+use crate::{HashMap, Hashable};
+
+/// Heap memory owned by a value, not counting the value itself.
+pub trait HeapSize {
+  fn heap_size(&self) -> usize;
+}
+
+macro_rules! no_heap {
+  ($($type:ty),*) => {
+    $(impl HeapSize for $type {
+      fn heap_size(&self) -> usize {
+        0
+      }
+    })*
+  };
+}
+
+no_heap!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, ());
+
+impl HeapSize for &str {
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+impl HeapSize for String {
+  fn heap_size(&self) -> usize {
+    self.capacity()
+  }
+}
+
+impl<T> HeapSize for Vec<T> where T: HeapSize {
+  fn heap_size(&self) -> usize {
+    self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+  }
+}
+
+impl<T> HeapSize for Box<T> where T: HeapSize {
+  fn heap_size(&self) -> usize {
+    size_of::<T>() + (**self).heap_size()
+  }
+}
+
+impl<T> HeapSize for Option<T> where T: HeapSize {
+  fn heap_size(&self) -> usize {
+    self.as_ref().map_or(0, HeapSize::heap_size)
+  }
+}
+
+impl<A, B> HeapSize for (A, B) where A: HeapSize, B: HeapSize {
+  fn heap_size(&self) -> usize {
+    self.0.heap_size() + self.1.heap_size()
+  }
+}
+
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + PartialEq {
+  /// Heap bytes held by the table itself: its slots and their metadata, whether occupied or not.
+  pub fn memory_usage(&self) -> usize {
+    self.entries.hashes.capacity() * size_of::<usize>()
+      + self.entries.keys.capacity() * size_of::<Option<Key>>()
+      + self.entries.values.capacity() * size_of::<Option<Value>>()
+      + self.occupancy.heap_size()
+      + self.control.heap_size()
+  }
+
+  /// `memory_usage` plus the heap memory owned by the stored keys and values.
+  pub fn deep_memory_usage(&self) -> usize where Key: HeapSize, Value: HeapSize {
+    self.memory_usage() + self.iter().map(|(key, value)| key.heap_size() + value.heap_size()).sum::<usize>()
+  }
+}
+
+impl<Key, Value> HeapSize for HashMap<Key, Value> where Key: Hashable + PartialEq + HeapSize, Value: HeapSize {
+  fn heap_size(&self) -> usize {
+    self.deep_memory_usage()
+  }
+}
@@ -0,0 +1,86 @@
+use crate::HashMap;
+
+const SEPARATOR: char = '.';
+
+#[derive(Debug)]
+struct Level<Value> {
+  children: HashMap<String, Level<Value>>,
+  values: HashMap<String, Value>,
+}
+
+impl<Value> Level<Value> {
+  fn new() -> Self {
+    Self {
+      children: HashMap::with_capacity(4),
+      values: HashMap::with_capacity(4),
+    }
+  }
+}
+
+/// Map of maps addressed by dotted paths such as `"server.tls.cert"`.
+///
+/// Every segment but the last names a nested level, each holding the levels below it by value.
+#[derive(Debug)]
+pub struct NestedMap<Value> {
+  root: Level<Value>,
+}
+
+impl<Value> Default for NestedMap<Value> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Value> NestedMap<Value> {
+  pub fn new() -> Self {
+    Self { root: Level::new() }
+  }
+
+  /// Inserts `value` at `path`, creating any missing intermediate levels.
+  pub fn insert_path(&mut self, path: &str, value: Value) {
+    let (parents, name) = split(path);
+    let mut level = &mut self.root;
+    for segment in parents {
+      level = level.children.entry_ref(segment).or_insert_with(Level::new);
+    }
+    level.values.insert(name.to_string(), value);
+  }
+
+  pub fn get_path(&self, path: &str) -> Option<&Value> {
+    let (parents, name) = split(path);
+    self.find_level(parents)?.values.get(name)
+  }
+
+  pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+    let (parents, name) = split(path);
+    let mut level = &mut self.root;
+    for segment in parents {
+      level = level.children.get_mut(segment)?;
+    }
+    level.values.get_mut(name)
+  }
+
+  pub fn contains_path(&self, path: &str) -> bool {
+    self.get_path(path).is_some()
+  }
+
+  /// Values stored directly under the level at `path`, with their last path segment as key.
+  pub fn level(&self, path: &str) -> Option<&HashMap<String, Value>> {
+    let level = self.find_level(path.split(SEPARATOR).filter(|segment| !segment.is_empty()))?;
+    Some(&level.values)
+  }
+
+  fn find_level<'a>(&self, segments: impl Iterator<Item = &'a str>) -> Option<&Level<Value>> {
+    let mut level = &self.root;
+    for segment in segments {
+      level = level.children.get(segment)?;
+    }
+    Some(level)
+  }
+}
+
+/// Splits `path` into its parent segments and the final name.
+fn split(path: &str) -> (impl Iterator<Item = &str>, &str) {
+  let (parents, name) = path.rsplit_once(SEPARATOR).unwrap_or(("", path));
+  (parents.split(SEPARATOR).filter(|segment| !segment.is_empty()), name)
+}
@@ -0,0 +1,93 @@
+use crate::{HashMap, Hashable};
+
+/// Two-level map `Outer -> Inner -> Value` with combined operations.
+///
+/// Stored as a `HashMap<Outer, HashMap<Inner, Value>>`; inner maps are created on first insert
+/// and dropped once their last entry is removed.
+#[derive(Debug)]
+pub struct NestedHashMap<Outer, Inner, Value> {
+  outer: HashMap<Outer, HashMap<Inner, Value>>,
+  len: usize,
+}
+
+impl<Outer, Inner, Value> Default for NestedHashMap<Outer, Inner, Value>
+where
+  Outer: Hashable + PartialEq,
+  Inner: Hashable + PartialEq,
+{
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Outer, Inner, Value> NestedHashMap<Outer, Inner, Value>
+where
+  Outer: Hashable + PartialEq,
+  Inner: Hashable + PartialEq,
+{
+  pub fn new() -> Self {
+    Self {
+      outer: HashMap::new(),
+      len: 0,
+    }
+  }
+
+  /// Number of `(outer, inner)` pairs stored.
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Inserts `value` under `(outer, inner)`, creating the inner map if needed, and returns the
+  /// value it replaced.
+  pub fn insert(&mut self, outer: Outer, inner: Inner, value: Value) -> Option<Value> {
+    let previous = self.outer.entry(outer).or_insert_with(|| HashMap::with_capacity(4)).insert(inner, value);
+    if previous.is_none() {
+      self.len += 1;
+    }
+    previous
+  }
+
+  pub fn get(&self, outer: &Outer, inner: &Inner) -> Option<&Value> {
+    self.inner_map(outer)?.get(inner)
+  }
+
+  pub fn get_mut(&mut self, outer: &Outer, inner: &Inner) -> Option<&mut Value> {
+    self.outer.get_mut(outer)?.get_mut(inner)
+  }
+
+  pub fn contains(&self, outer: &Outer, inner: &Inner) -> bool {
+    self.get(outer, inner).is_some()
+  }
+
+  /// Removes `(outer, inner)`, dropping the inner map once it becomes empty.
+  pub fn remove_inner(&mut self, outer: &Outer, inner: &Inner) -> Option<Value> {
+    let map = self.outer.get_mut(outer)?;
+    let value = map.remove(inner)?;
+    self.len -= 1;
+    if map.is_empty() {
+      self.outer.remove(outer);
+    }
+    Some(value)
+  }
+
+  /// Removes every entry under `outer`.
+  pub fn remove_outer(&mut self, outer: &Outer) -> Option<HashMap<Inner, Value>> {
+    let map = self.outer.remove(outer)?;
+    self.len -= map.len();
+    Some(map)
+  }
+
+  /// The inner map stored under `outer`.
+  pub fn inner_map(&self, outer: &Outer) -> Option<&HashMap<Inner, Value>> {
+    self.outer.get(outer)
+  }
+
+  /// Every `(outer, inner, value)` triple.
+  pub fn iter(&self) -> impl Iterator<Item = (&Outer, &Inner, &Value)> {
+    self.outer.iter().flat_map(|(outer, map)| map.iter().map(move |(inner, value)| (outer, inner, value)))
+  }
+}
@@ -0,0 +1,59 @@
+/// One bit per slot, so scans can skip whole words of empty slots with `trailing_zeros`.
+#[derive(Debug, Default)]
+pub(crate) struct Occupancy {
+  words: Vec<u64>,
+}
+
+impl Clone for Occupancy {
+  fn clone(&self) -> Self {
+    Self { words: self.words.clone() }
+  }
+
+  fn clone_from(&mut self, source: &Self) {
+    self.words.clone_from(&source.words);
+  }
+}
+
+impl Occupancy {
+  pub(crate) fn with_slots(slots: usize) -> Self {
+    Self {
+      words: vec![0; slots.div_ceil(64)],
+    }
+  }
+
+  pub(crate) fn try_with_slots(slots: usize) -> Result<Self, std::collections::TryReserveError> {
+    let mut words = Vec::new();
+    words.try_reserve_exact(slots.div_ceil(64))?;
+    words.resize(slots.div_ceil(64), 0);
+    Ok(Self { words })
+  }
+
+  pub(crate) fn heap_size(&self) -> usize {
+    self.words.capacity() * size_of::<u64>()
+  }
+
+  pub(crate) fn set(&mut self, index: usize) {
+    self.words[index / 64] |= 1 << (index % 64);
+  }
+
+  pub(crate) fn clear(&mut self, index: usize) {
+    self.words[index / 64] &= !(1 << (index % 64));
+  }
+
+  pub(crate) fn clear_all(&mut self) {
+    self.words.fill(0);
+  }
+
+  /// First occupied slot at or after `index`.
+  pub(crate) fn next_from(&self, index: usize) -> Option<usize> {
+    let mut word_index = index / 64;
+    let mut word = *self.words.get(word_index)? & (!0 << (index % 64));
+    loop {
+      if word != 0 {
+        return Some(word_index * 64 + word.trailing_zeros() as usize);
+      }
+      word_index += 1;
+      word = *self.words.get(word_index)?;
+    }
+  }
+}
@@ -0,0 +1,60 @@
+use crate::{HashMap, Hashable};
+
+/// Stack of maps resolved in precedence order, e.g. defaults < file < env < CLI.
+///
+/// Layers are pushed from lowest to highest precedence; lookups start at the top.
+#[derive(Debug)]
+pub struct OverlayMap<Key, Value> {
+  layers: Vec<HashMap<Key, Value>>,
+}
+
+impl<Key, Value> Default for OverlayMap<Key, Value> where Key: Hashable + PartialEq {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Key, Value> OverlayMap<Key, Value> where Key: Hashable + PartialEq {
+  pub fn new() -> Self {
+    Self { layers: vec![] }
+  }
+
+  /// Adds `layer` above every existing layer.
+  pub fn push_layer(&mut self, layer: HashMap<Key, Value>) {
+    self.layers.push(layer);
+  }
+
+  pub fn with_layer(mut self, layer: HashMap<Key, Value>) -> Self {
+    self.push_layer(layer);
+    self
+  }
+
+  pub fn layers(&self) -> &[HashMap<Key, Value>] {
+    &self.layers
+  }
+
+  pub fn layer_mut(&mut self, index: usize) -> Option<&mut HashMap<Key, Value>> {
+    self.layers.get_mut(index)
+  }
+
+  /// Value from the highest layer that has `key`.
+  pub fn get(&self, key: &Key) -> Option<&Value> {
+    self.layers.iter().rev().find_map(|layer| layer.get(key))
+  }
+
+  /// Index of the layer `get` would answer from.
+  pub fn source(&self, key: &Key) -> Option<usize> {
+    self.layers.iter().rposition(|layer| layer.contains_key(key))
+  }
+
+  /// Materializes the resolved view into a single map.
+  pub fn flatten(&self) -> HashMap<Key, Value> where Key: Clone, Value: Clone {
+    let mut flat = HashMap::new();
+    for layer in self.layers.iter() {
+      for (key, value) in layer.iter() {
+        flat.insert(key.clone(), value.clone());
+      }
+    }
+    flat
+  }
+}
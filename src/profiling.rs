@@ -0,0 +1,101 @@
+use std::ops::Add;
+#[cfg(feature = "profiling")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::HashMap;
+
+/// Operation counters of a single map, collected when the `profiling` feature is enabled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+  /// Slots inspected while probing.
+  pub probes: u64,
+  /// Key equality checks performed.
+  pub comparisons: u64,
+  /// Times the table was rehashed into a new size, whether growing or shrinking.
+  pub resizes: u64,
+}
+
+impl Add for Metrics {
+  type Output = Metrics;
+
+  fn add(self, other: Metrics) -> Metrics {
+    Metrics {
+      probes: self.probes + other.probes,
+      comparisons: self.comparisons + other.comparisons,
+      resizes: self.resizes + other.resizes,
+    }
+  }
+}
+
+/// `Metrics` kept in atomics, so lookups can count through `&self` and the map stays `Sync`.
+#[cfg(feature = "profiling")]
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+  probes: AtomicU64,
+  comparisons: AtomicU64,
+  resizes: AtomicU64,
+}
+
+#[cfg(feature = "profiling")]
+impl Counters {
+  // The counters are independent statistics, so no ordering between them is needed.
+  pub(crate) fn load(&self) -> Metrics {
+    Metrics {
+      probes: self.probes.load(Ordering::Relaxed),
+      comparisons: self.comparisons.load(Ordering::Relaxed),
+      resizes: self.resizes.load(Ordering::Relaxed),
+    }
+  }
+
+  pub(crate) fn store(&self, metrics: Metrics) {
+    self.probes.store(metrics.probes, Ordering::Relaxed);
+    self.comparisons.store(metrics.comparisons, Ordering::Relaxed);
+    self.resizes.store(metrics.resizes, Ordering::Relaxed);
+  }
+
+  fn add(&self, delta: Metrics) {
+    for (counter, amount) in [(&self.probes, delta.probes), (&self.comparisons, delta.comparisons), (&self.resizes, delta.resizes)] {
+      if amount != 0 {
+        counter.fetch_add(amount, Ordering::Relaxed);
+      }
+    }
+  }
+}
+
+#[cfg(feature = "profiling")]
+impl Clone for Counters {
+  fn clone(&self) -> Self {
+    let counters = Counters::default();
+    counters.store(self.load());
+    counters
+  }
+
+  fn clone_from(&mut self, source: &Self) {
+    self.store(source.load());
+  }
+}
+
+#[allow(dead_code)]
+impl<Key, Value> HashMap<Key, Value> {
+  #[cfg(feature = "profiling")]
+  #[inline]
+  pub(crate) fn record(&self, update: impl FnOnce(&mut Metrics)) {
+    let mut delta = Metrics::default();
+    update(&mut delta);
+    self.metrics.add(delta);
+  }
+
+  #[cfg(not(feature = "profiling"))]
+  #[inline(always)]
+  pub(crate) fn record(&self, _update: impl FnOnce(&mut Metrics)) {}
+
+  #[cfg(feature = "profiling")]
+  pub fn metrics(&self) -> Metrics {
+    self.metrics.load()
+  }
+
+  #[cfg(feature = "profiling")]
+  pub fn reset_metrics(&self) {
+    self.metrics.store(Metrics::default());
+  }
+}
@@ -0,0 +1,82 @@
+use crate::{table_hash, HashMap, Hashable, OccupiedEntry};
+
+/// Looks up entries by a precomputed hash, obtained with `HashMap::raw_entry`.
+pub struct RawEntryBuilder<'a, Key, Value> {
+  pub(crate) map: &'a HashMap<Key, Value>,
+}
+
+/// Looks up or inserts entries by a precomputed hash, obtained with `HashMap::raw_entry_mut`.
+pub struct RawEntryBuilderMut<'a, Key, Value> {
+  pub(crate) map: &'a mut HashMap<Key, Value>,
+}
+
+/// A view into a single slot, located without hashing the key again.
+pub enum RawEntryMut<'a, Key, Value> {
+  Occupied(OccupiedEntry<'a, Key, Value>),
+  Vacant(RawVacantEntryMut<'a, Key, Value>),
+}
+
+/// Holds the hash of a missing key; the table grows, if full, when the entry is inserted.
+pub struct RawVacantEntryMut<'a, Key, Value> {
+  pub(crate) map: &'a mut HashMap<Key, Value>,
+  pub(crate) hash: usize,
+}
+
+impl<'a, Key, Value> RawEntryBuilder<'a, Key, Value> where Key: Hashable + PartialEq {
+  /// Finds the entry whose key has the given `hash` and satisfies `is_match`.
+  ///
+  /// `hash` must be the `Hashable::hash` of the key being looked up.
+  pub fn from_hash<F: FnMut(&Key) -> bool>(self, hash: usize, is_match: F) -> Option<(&'a Key, &'a Value)> {
+    let hash = table_hash(hash);
+    self.map.entries.get(self.map.find_slot_by(hash, is_match)?)
+  }
+
+  pub fn from_key(self, key: &Key) -> Option<(&'a Key, &'a Value)> {
+    self.from_hash(key.hash(), |stored| stored == key)
+  }
+}
+
+impl<'a, Key, Value> RawEntryBuilderMut<'a, Key, Value> where Key: Hashable + PartialEq {
+  /// Finds the entry whose key has the given `hash` and satisfies `is_match`, or a vacant entry
+  /// to insert a key with that hash.
+  ///
+  /// `hash` must be the `Hashable::hash` of the key being looked up.
+  pub fn from_hash<F: FnMut(&Key) -> bool>(self, hash: usize, is_match: F) -> RawEntryMut<'a, Key, Value> {
+    let hash = table_hash(hash);
+    match self.map.find_slot_by(hash, is_match) {
+      Some(index) => RawEntryMut::Occupied(OccupiedEntry { map: self.map, index }),
+      None => RawEntryMut::Vacant(RawVacantEntryMut { map: self.map, hash }),
+    }
+  }
+
+  pub fn from_key(self, key: &Key) -> RawEntryMut<'a, Key, Value> {
+    self.from_hash(key.hash(), |stored| stored == key)
+  }
+}
+
+impl<'a, Key, Value> RawEntryMut<'a, Key, Value> where Key: Hashable + PartialEq {
+  pub fn or_insert(self, key: Key, value: Value) -> &'a mut Value {
+    match self {
+      RawEntryMut::Occupied(entry) => entry.into_mut(),
+      RawEntryMut::Vacant(entry) => entry.insert(key, value),
+    }
+  }
+
+  pub fn or_insert_with<F: FnOnce() -> (Key, Value)>(self, default: F) -> &'a mut Value {
+    match self {
+      RawEntryMut::Occupied(entry) => entry.into_mut(),
+      RawEntryMut::Vacant(entry) => {
+        let (key, value) = default();
+        entry.insert(key, value)
+      }
+    }
+  }
+}
+
+impl<'a, Key, Value> RawVacantEntryMut<'a, Key, Value> where Key: Hashable + PartialEq {
+  /// Stores `key` and `value`; `key` must hash to the hash this entry was found by.
+  pub fn insert(self, key: Key, value: Value) -> &'a mut Value {
+    debug_assert_eq!(table_hash(key.hash()), self.hash, "key does not match the hash it was looked up by");
+    self.map.insert_absent(self.hash, key, value)
+  }
+}
@@ -0,0 +1,68 @@
+use std::io::{self, BufRead, Write};
+
+use crate::HashMap;
+
+const HELP: &str = "commands:
+  insert <key> <value>  insert or replace a key
+  get <key>             print the value of a key
+  remove <key>          remove a key
+  list                  print all entries sorted by key
+  stats                 print length, bucket count, load and probe lengths
+  dump                  print every slot of the table
+  help                  print this help
+  quit                  exit";
+
+/// Reads commands from `input` line by line and drives a `HashMap<String, String>` with them.
+pub fn run(input: impl BufRead, mut output: impl Write, prompt: bool) -> io::Result<()> {
+  let mut map = HashMap::<String, String>::with_capacity(8);
+  if prompt {
+    writeln!(output, "rusty-map REPL, type `help` for commands")?;
+    write!(output, "> ")?;
+    output.flush()?;
+  }
+  for line in input.lines() {
+    let line = line?;
+    let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+    let args = args.trim();
+    match command {
+      "" => {}
+      "insert" => match args.split_once(' ') {
+        Some((key, value)) => {
+          map.insert(key.to_string(), value.trim().to_string());
+          writeln!(output, "ok")?;
+        }
+        None => writeln!(output, "usage: insert <key> <value>")?,
+      },
+      "get" if !args.is_empty() => match map.get(args) {
+        Some(value) => writeln!(output, "{}", value)?,
+        None => writeln!(output, "(none)")?,
+      },
+      "remove" if !args.is_empty() => match map.remove(args) {
+        Some(value) => writeln!(output, "{}", value)?,
+        None => writeln!(output, "(none)")?,
+      },
+      "get" | "remove" => writeln!(output, "usage: {} <key>", command)?,
+      "list" => writeln!(output, "{}", map.pretty())?,
+      "stats" => {
+        let max_displacement = map.dump().slots.iter().filter_map(|slot| slot.displacement).max().unwrap_or(0);
+        writeln!(
+          output,
+          "len={} buckets={} load={:.2} max_displacement={}",
+          map.len(),
+          map.bucket_count(),
+          map.len() as f64 / map.bucket_count() as f64,
+          max_displacement
+        )?;
+      }
+      "dump" => write!(output, "{}", map.dump())?,
+      "help" => writeln!(output, "{}", HELP)?,
+      "quit" | "exit" => break,
+      _ => writeln!(output, "unknown command `{}`, type `help` for commands", command)?,
+    }
+    if prompt {
+      write!(output, "> ")?;
+      output.flush()?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,136 @@
+use rand::Rng;
+
+use crate::{HashMap, Hashable};
+
+/// Random slot probes attempted before falling back to picking the n-th occupied slot.
+const MAX_REJECTIONS: usize = 16;
+
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + PartialEq {
+  /// Picks an occupied entry uniformly at random.
+  ///
+  /// Slots are drawn at random until an occupied one is hit, which takes about
+  /// `capacity / len` tries. Tables too sparse for that to pay off scan for a random ordinal instead.
+  pub fn random_entry<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(&Key, &Value)> {
+    if self.occupied == 0 {
+      return None;
+    }
+    if self.occupied * MAX_REJECTIONS >= self.entries.len() {
+      for _ in 0..MAX_REJECTIONS {
+        if let Some(entry) = self.entries.get(rng.gen_range(0..self.entries.len())) {
+          return Some(entry);
+        }
+      }
+    }
+    self.iter().nth(rng.gen_range(0..self.occupied))
+  }
+
+  /// Picks `amount` distinct entries uniformly at random, or every entry if the map is smaller.
+  pub fn sample<R: Rng + ?Sized>(&self, amount: usize, rng: &mut R) -> Vec<(&Key, &Value)> {
+    let entries: Vec<(&Key, &Value)> = self.iter().collect();
+    rand::seq::index::sample(rng, entries.len(), amount.min(entries.len()))
+      .into_iter()
+      .map(|index| entries[index])
+      .collect()
+  }
+
+  /// Reservoir-samples `amount` entries in a single pass, without collecting every entry first.
+  pub fn sample_iter<R: Rng + ?Sized>(&self, amount: usize, rng: &mut R) -> Vec<(&Key, &Value)> {
+    reservoir_sample(self.iter(), amount, rng)
+  }
+
+  /// Picks a key with probability proportional to `weight(value)`. Non-positive weights are never picked.
+  ///
+  /// This is a single linear pass; build a `weighted_sampler` when drawing repeatedly.
+  pub fn sample_weighted<R: Rng + ?Sized, F: Fn(&Value) -> f64>(&self, rng: &mut R, weight: F) -> Option<&Key> {
+    let total: f64 = self.iter().map(|(_, value)| clamp_weight(weight(value))).sum();
+    if total <= 0.0 {
+      return None;
+    }
+    let mut target = rng.gen_range(0.0..total);
+    let mut last = None;
+    for (key, value) in self.iter() {
+      let weight = clamp_weight(weight(value));
+      if weight > 0.0 {
+        if target < weight {
+          return Some(key);
+        }
+        target -= weight;
+        last = Some(key);
+      }
+    }
+    last
+  }
+
+  /// Precomputes an alias table so every following draw is O(1).
+  pub fn weighted_sampler<F: Fn(&Value) -> f64>(&self, weight: F) -> WeightedSampler<'_, Key> {
+    let (keys, weights): (Vec<&Key>, Vec<f64>) = self.iter()
+      .map(|(key, value)| (key, clamp_weight(weight(value))))
+      .filter(|(_, weight)| *weight > 0.0)
+      .unzip();
+    WeightedSampler::new(keys, weights)
+  }
+}
+
+fn clamp_weight(weight: f64) -> f64 {
+  if weight.is_finite() && weight > 0.0 {
+    weight
+  } else {
+    0.0
+  }
+}
+
+/// Vose's alias method over a snapshot of the map's keys.
+pub struct WeightedSampler<'a, Key> {
+  keys: Vec<&'a Key>,
+  probability: Vec<f64>,
+  alias: Vec<usize>,
+}
+
+impl<'a, Key> WeightedSampler<'a, Key> {
+  fn new(keys: Vec<&'a Key>, weights: Vec<f64>) -> Self {
+    let count = weights.len();
+    let total: f64 = weights.iter().sum();
+    let mut scaled: Vec<f64> = weights.iter().map(|weight| weight * count as f64 / total).collect();
+    let mut probability = vec![1.0; count];
+    let mut alias: Vec<usize> = (0..count).collect();
+    let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..count).partition(|&index| scaled[index] < 1.0);
+    while let (Some(less), Some(&more)) = (small.pop(), large.last()) {
+      probability[less] = scaled[less];
+      alias[less] = more;
+      scaled[more] -= 1.0 - scaled[less];
+      if scaled[more] < 1.0 {
+        large.pop();
+        small.push(more);
+      }
+    }
+    Self { keys, probability, alias }
+  }
+
+  pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&'a Key> {
+    if self.keys.is_empty() {
+      return None;
+    }
+    let column = rng.gen_range(0..self.keys.len());
+    if rng.gen::<f64>() < self.probability[column] {
+      Some(self.keys[column])
+    } else {
+      Some(self.keys[self.alias[column]])
+    }
+  }
+}
+
+/// Algorithm R: keeps each of the items seen so far in the reservoir with equal probability.
+pub fn reservoir_sample<T, I: Iterator<Item = T>, R: Rng + ?Sized>(items: I, amount: usize, rng: &mut R) -> Vec<T> {
+  let mut reservoir = Vec::with_capacity(amount);
+  for (seen, item) in items.enumerate() {
+    if seen < amount {
+      reservoir.push(item);
+    } else {
+      let slot = rng.gen_range(0..=seen);
+      if slot < amount {
+        reservoir[slot] = item;
+      }
+    }
+  }
+  reservoir
+}
@@ -0,0 +1,108 @@
+use crate::{HashMap, Hashable};
+
+/// Map that stores up to `N` entries inline, found by a linear scan, and moves them into a
+/// `HashMap` once an insert would exceed `N`.
+///
+/// Maps that stay small therefore never allocate. A map that has spilled keeps its heap table
+/// even if removals bring it back under `N`.
+pub struct SmallMap<Key, Value, const N: usize> {
+  storage: Storage<Key, Value, N>,
+}
+
+enum Storage<Key, Value, const N: usize> {
+  /// The first `len` slots are occupied and the rest are `None`.
+  Inline { slots: [Option<(Key, Value)>; N], len: usize },
+  Heap(HashMap<Key, Value>),
+}
+
+impl<Key, Value, const N: usize> Default for SmallMap<Key, Value, N> where Key: Hashable + PartialEq {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Key, Value, const N: usize> SmallMap<Key, Value, N> where Key: Hashable + PartialEq {
+  pub fn new() -> Self {
+    Self {
+      storage: Storage::Inline { slots: std::array::from_fn(|_| None), len: 0 },
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    match &self.storage {
+      Storage::Inline { len, .. } => *len,
+      Storage::Heap(map) => map.len(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Whether the entries are still stored inline rather than in a heap table.
+  pub fn is_inline(&self) -> bool {
+    matches!(self.storage, Storage::Inline { .. })
+  }
+
+  pub fn get(&self, key: &Key) -> Option<&Value> {
+    match &self.storage {
+      Storage::Inline { slots, len } => slots[..*len].iter().flatten().find(|(stored, _)| stored == key).map(|(_, value)| value),
+      Storage::Heap(map) => map.get(key),
+    }
+  }
+
+  pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+    match &mut self.storage {
+      Storage::Inline { slots, len } => slots[..*len].iter_mut().flatten().find(|(stored, _)| stored == key).map(|(_, value)| value),
+      Storage::Heap(map) => map.get_mut(key),
+    }
+  }
+
+  pub fn contains_key(&self, key: &Key) -> bool {
+    self.get(key).is_some()
+  }
+
+  /// Inserts `value` under `key`, returning the value it replaced, if any.
+  pub fn insert(&mut self, key: Key, value: Value) -> Option<Value> {
+    if let Some(old) = self.get_mut(&key) {
+      return Some(std::mem::replace(old, value));
+    }
+    match &mut self.storage {
+      Storage::Inline { slots, len } if *len < N => {
+        slots[*len] = Some((key, value));
+        *len += 1;
+      }
+      Storage::Inline { slots, .. } => {
+        let mut map = HashMap::with_capacity_for(N + 1);
+        map.insert_batch(slots.iter_mut().filter_map(Option::take));
+        map.insert(key, value);
+        self.storage = Storage::Heap(map);
+      }
+      Storage::Heap(map) => {
+        map.insert(key, value);
+      }
+    }
+    None
+  }
+
+  pub fn remove(&mut self, key: &Key) -> Option<Value> {
+    match &mut self.storage {
+      Storage::Inline { slots, len } => {
+        let index = slots[..*len].iter().flatten().position(|(stored, _)| stored == key)?;
+        // Keeps the occupied slots packed at the front.
+        *len -= 1;
+        slots.swap(index, *len);
+        slots[*len].take().map(|(_, value)| value)
+      }
+      Storage::Heap(map) => map.remove(key),
+    }
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+    let (inline, heap) = match &self.storage {
+      Storage::Inline { slots, len } => (Some(slots[..*len].iter().flatten().map(|(key, value)| (key, value))), None),
+      Storage::Heap(map) => (None, Some(map.iter())),
+    };
+    inline.into_iter().flatten().chain(heap.into_iter().flatten())
+  }
+}
@@ -0,0 +1,74 @@
+use std::any::{type_name, Any};
+use std::fmt::{self, Debug};
+
+use crate::HashMap;
+
+struct Var {
+  value: Box<dyn Any + Send>,
+  type_name: &'static str,
+}
+
+/// String-keyed map whose values may each have a different type.
+///
+/// Values live in a side vector indexed through a `HashMap<String, usize>`, each boxed as
+/// `dyn Any` next to the name of its type.
+#[derive(Default)]
+pub struct VarMap {
+  index: HashMap<String, usize>,
+  vars: Vec<Var>,
+}
+
+impl VarMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.vars.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.vars.is_empty()
+  }
+
+  /// Stores `value` under `key`, replacing any previous value whatever its type.
+  pub fn insert<T: Any + Send>(&mut self, key: &str, value: T) {
+    let var = Var {
+      value: Box::new(value),
+      type_name: type_name::<T>(),
+    };
+    if let Some(&index) = self.index.get(key) {
+      self.vars[index] = var;
+    } else {
+      self.index.insert(key.to_string(), self.vars.len());
+      self.vars.push(var);
+    }
+  }
+
+  /// Returns the value under `key` if it exists and was stored as a `T`.
+  pub fn get<T: Any>(&self, key: &str) -> Option<&T> {
+    let index = *self.index.get(key)?;
+    self.vars[index].value.downcast_ref::<T>()
+  }
+
+  pub fn get_mut<T: Any>(&mut self, key: &str) -> Option<&mut T> {
+    let index = *self.index.get(key)?;
+    self.vars[index].value.downcast_mut::<T>()
+  }
+
+  pub fn contains_key(&self, key: &str) -> bool {
+    self.index.contains_key(key)
+  }
+
+  /// Name of the type the value under `key` was inserted with.
+  pub fn type_name(&self, key: &str) -> Option<&'static str> {
+    let index = *self.index.get(key)?;
+    Some(self.vars[index].type_name)
+  }
+}
+
+impl Debug for VarMap {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_map().entries(self.index.iter().map(|(key, &index)| (key, self.vars[index].type_name))).finish()
+  }
+}
@@ -0,0 +1,53 @@
+use std::fmt::{Debug, Write};
+
+use crate::{HashMap, Hashable};
+
+const CELL: usize = 16;
+const COLUMNS: usize = 32;
+
+#[allow(dead_code)]
+impl<Key, Value> HashMap<Key, Value> where Key: Hashable + Debug + PartialEq {
+  /// Renders bucket occupancy as an SVG heatmap.
+  ///
+  /// Empty slots are grey, occupied slots go from green to red as their displacement from the
+  /// home bucket grows, and every displaced entry gets a line back to its home bucket so probe
+  /// chains and clusters are visible at a glance.
+  pub fn visualize(&self) -> String {
+    let report = self.dump();
+    let rows = report.slots.len().div_ceil(COLUMNS).max(1);
+    let max_displacement = report.slots.iter().filter_map(|slot| slot.displacement).max().unwrap_or(0).max(1);
+    let center = |index: usize| (index % COLUMNS * CELL + CELL / 2, index / COLUMNS * CELL + CELL / 2);
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#, COLUMNS * CELL, rows * CELL);
+    for slot in report.slots.iter() {
+      let (x, y) = (slot.index % COLUMNS * CELL, slot.index / COLUMNS * CELL);
+      let (fill, title) = match (slot.key, slot.home, slot.displacement) {
+        (Some(key), Some(home), Some(displacement)) => {
+          let hue = 120 - 120 * displacement / max_displacement;
+          (format!("hsl({}, 70%, 50%)", hue), format!("{}: {:?} home={} displacement={}", slot.index, key, home, displacement))
+        }
+        _ => ("#eeeeee".to_string(), format!("{}: Empty", slot.index)),
+      };
+      let _ = writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="white"><title>{}</title></rect>"#,
+        x, y, CELL, CELL, fill, escape(&title)
+      );
+    }
+    for slot in report.slots.iter() {
+      if let (Some(home), Some(displacement)) = (slot.home, slot.displacement) {
+        if displacement > 0 {
+          let ((x1, y1), (x2, y2)) = (center(home), center(slot.index));
+          let _ = writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black" stroke-opacity="0.4"/>"#, x1, y1, x2, y2);
+        }
+      }
+    }
+    svg.push_str("</svg>\n");
+    svg
+  }
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}